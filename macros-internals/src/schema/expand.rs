@@ -101,10 +101,19 @@ impl ToTokens for SchemaDefinition {
 
             // non-advanced reprs
             ReprDefinition::Bytes(def) => expand_basic!(meta, def),
-            // ReprDefinition::List(def) => expand_basic_def!(meta, def),
-            // ReprDefinition::Map(def) => expand_basic_def!(meta, def),
+            ReprDefinition::List(ListReprDefinition::Advanced(def)) => syn::Error::new_spanned(
+                &def.name,
+                "advanced list representations are not yet supported",
+            )
+            .to_compile_error(),
+            ReprDefinition::List(def) => expand_basic!(meta, def),
+            ReprDefinition::Map(MapReprDefinition::Advanced(def)) => syn::Error::new_spanned(
+                &def.name,
+                "advanced map representations are not yet supported",
+            )
+            .to_compile_error(),
+            ReprDefinition::Map(def) => expand_basic!(meta, def),
             ReprDefinition::Struct(def) => expand_basic!(meta, def),
-            _ => unimplemented!(),
         });
     }
 }
@@ -314,6 +323,27 @@ pub(crate) fn impl_repr(meta: &SchemaMeta, body: TokenStream) -> TokenStream {
     }
 }
 
+/// Implements `Flush` for the type, including any of its generic parameters.
+///
+/// The body is generic over the multihash size `__S`, named so as not to
+/// collide with the type's own generic parameters.
+pub(crate) fn impl_flush(meta: &SchemaMeta, body: TokenStream) -> TokenStream {
+    let name = &meta.name;
+    let generics = meta.generics.clone().unwrap_or_default();
+    let mut flush_generics = generics.clone();
+    flush_generics.params.push(parse_quote!(__S: MultihashSize));
+    let (impl_generics, _, _) = flush_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics Flush<__S> for #name #ty_generics #where_clause {
+            fn flush_blocks(&mut self, blocks: &mut Vec<Block<__S>>) -> Result<bool, Error> {
+                #body
+            }
+        }
+    }
+}
+
 pub(crate) fn impl_select_for(
     meta: &SchemaMeta,
     selector: Ident,
@@ -1 +1,224 @@
+use super::*;
+use crate::dev::{
+    schema::expand::{self, ExpandBasicRepresentation},
+    SchemaMeta,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{LitStr, Type};
 
+impl ExpandBasicRepresentation for ListReprDefinition {
+    fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
+        let inner_type = list_type(self);
+        crate::define_newtype!(self, meta => inner_type)
+    }
+    fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
+        let inner_type = list_type(self);
+        let repr = expand::impl_repr(meta, TokenStream::default());
+        let flush = expand::impl_flush(
+            meta,
+            quote!(<#inner_type as Flush<__S>>::flush_blocks(&mut self.0, blocks)),
+        );
+        quote! {
+            #repr
+            #flush
+        }
+    }
+}
+
+/// The `Vec` type wrapped by a list typedef.
+fn list_type(def: &ListReprDefinition) -> Type {
+    let (elem, nullable) = def.elem_type();
+    if nullable {
+        Type::Verbatim(quote!(Vec<Option<#elem>>))
+    } else {
+        Type::Verbatim(quote!(Vec<#elem>))
+    }
+}
+
+impl ExpandBasicRepresentation for MapReprDefinition {
+    fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
+        let inner_type = map_type(self);
+        match self {
+            Self::Basic { .. } => crate::define_newtype!(self, meta => inner_type),
+            _ => {
+                let attrs = &meta.attrs;
+                let vis = &meta.vis;
+                let ident = &meta.name;
+                quote! {
+                    #(#attrs)*
+                    #vis struct #ident(#inner_type);
+
+                    impl ::std::ops::Deref for #ident {
+                        type Target = #inner_type;
+                        fn deref(&self) -> &Self::Target {
+                            &self.0
+                        }
+                    }
+                }
+            }
+        }
+    }
+    fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
+        match self {
+            Self::Listpairs { .. } => derive_listpairs_serde(self, meta),
+            Self::Stringpairs {
+                inner_delim,
+                entry_delim,
+                ..
+            } => derive_stringpairs_serde(self, meta, inner_delim, entry_delim),
+            _ => TokenStream::default(),
+        }
+    }
+    fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
+        let inner_type = map_type(self);
+        let repr = expand::impl_repr(meta, TokenStream::default());
+        let flush = expand::impl_flush(
+            meta,
+            quote!(<#inner_type as Flush<__S>>::flush_blocks(&mut self.0, blocks)),
+        );
+        quote! {
+            #repr
+            #flush
+        }
+    }
+}
+
+/// The `BTreeMap` type wrapped by a map typedef.
+fn map_type(def: &MapReprDefinition) -> Type {
+    let (key, value, nullable) = def.field_type();
+    if nullable {
+        Type::Verbatim(quote!(::std::collections::BTreeMap<#key, Option<#value>>))
+    } else {
+        Type::Verbatim(quote!(::std::collections::BTreeMap<#key, #value>))
+    }
+}
+
+/// Serializes the map as a list of `[key, value]` pairs.
+fn derive_listpairs_serde(def: &MapReprDefinition, meta: &SchemaMeta) -> TokenStream {
+    let name = &meta.name;
+    let (key, value, nullable) = def.field_type();
+    let value = if nullable {
+        quote!(Option<#value>)
+    } else {
+        quote!(#value)
+    };
+
+    let impl_ser = expand::impl_serialize(
+        meta,
+        quote! {
+            serializer.collect_seq(self.0.iter())
+        },
+    );
+    let (visitor, impl_visitor) = expand::impl_visitor(
+        meta,
+        "an IPLD map as a list of [key, value] pairs",
+        quote! {
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut map = ::std::collections::BTreeMap::new();
+                while let Some((key, value)) = seq.next_element::<(#key, #value)>()? {
+                    if map.insert(key, value).is_some() {
+                        return Err(de::Error::custom("duplicate map key"));
+                    }
+                }
+                Ok(#name(map))
+            }
+        },
+    );
+    let impl_de = expand::impl_deserialize(
+        meta,
+        quote! {
+            deserializer.deserialize_seq(#visitor)
+        },
+    );
+
+    quote! {
+        #impl_ser
+        #impl_visitor
+        #impl_de
+    }
+}
+
+/// Serializes the map as a single string of `key{inner}value` entries,
+/// separated by `entry`.
+fn derive_stringpairs_serde(
+    def: &MapReprDefinition,
+    meta: &SchemaMeta,
+    inner: &LitStr,
+    entry: &LitStr,
+) -> TokenStream {
+    let name = &meta.name;
+    let (key, value, nullable) = def.field_type();
+    let value = if nullable {
+        quote!(Option<#value>)
+    } else {
+        quote!(#value)
+    };
+
+    let impl_ser = expand::impl_serialize(
+        meta,
+        quote! {
+            let delims = &[#inner, #entry];
+            let mut s = String::new();
+            for (i, (key, value)) in self.0.iter().enumerate() {
+                let key = to_delimited(key, "map key", delims)
+                    .map_err(<S::Error as serde::ser::Error>::custom)?;
+                let value = to_delimited(value, "map value", delims)
+                    .map_err(<S::Error as serde::ser::Error>::custom)?;
+                if i > 0 {
+                    s.push_str(#entry);
+                }
+                s.push_str(&key);
+                s.push_str(#inner);
+                s.push_str(&value);
+            }
+            serializer.serialize_str(&s)
+        },
+    );
+    let (visitor, impl_visitor) = expand::impl_visitor(
+        meta,
+        "an IPLD map as a string of delimited key-value pairs",
+        quote! {
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                let mut map = ::std::collections::BTreeMap::new();
+                if s.is_empty() {
+                    return Ok(#name(map));
+                }
+
+                for pair in s.split(#entry) {
+                    let mut pair = pair.splitn(2, #inner);
+                    let (key, value) = match (pair.next(), pair.next()) {
+                        (Some(key), Some(value)) => (key, value),
+                        _ => return Err(E::custom(::std::format!(
+                            "map entry is missing the delimiter {:?}",
+                            #inner
+                        ))),
+                    };
+                    let key = from_delimited::<#key>(key, "map key")
+                        .map_err(E::custom)?;
+                    let value = from_delimited::<#value>(value, "map value")
+                        .map_err(E::custom)?;
+                    if map.insert(key, value).is_some() {
+                        return Err(E::custom("duplicate map key"));
+                    }
+                }
+                Ok(#name(map))
+            }
+        },
+    );
+    let impl_de = expand::impl_deserialize(
+        meta,
+        quote! {
+            deserializer.deserialize_str(#visitor)
+        },
+    );
+
+    quote! {
+        #impl_ser
+        #impl_visitor
+        #impl_de
+    }
+}
@@ -44,7 +44,6 @@ fn field_typedef(field: &StructField) -> TokenStream {
     let vis = &field.vis;
     let key = &field.key;
     let value = field_value(field);
-    let generics = field.generics.as_ref().map(|g| quote!(#g));
 
     let implicit_attr = field.implicit.as_ref().map(|_| quote!(#[serde(default)]));
     let rename_attr = field
@@ -56,7 +55,7 @@ fn field_typedef(field: &StructField) -> TokenStream {
         #(#attrs)*
         #implicit_attr
         #rename_attr
-        #vis #key: #value #generics
+        #vis #key: #value
     }
 }
 
@@ -66,7 +65,10 @@ pub(super) fn impl_repr<'a>(
 ) -> TokenStream {
     // let lib = &meta.ipld_schema_lib;
     let name = &meta.name;
+    let iter: Vec<&StructField> = iter.collect();
+    let flush_impl = impl_flush(&iter, meta);
     let fields: Vec<TokenStream> = iter
+        .into_iter()
         .map(
             |StructField {
                  key, value, rename, ..
@@ -91,11 +93,65 @@ pub(super) fn impl_repr<'a>(
         },
     );
     let selector_bodies = quote! {};
-    repr_body
+    quote! {
+        #repr_body
+        #flush_impl
+    }
 }
 
-pub(super) fn field_value(field: &StructField) -> TokenStream {
+/// Implements `Flush` for structs, flushing each field in order.
+fn impl_flush(fields: &[&StructField], meta: &SchemaMeta) -> TokenStream {
+    let keys = fields.iter().map(|field| &field.key);
+    expand::impl_flush(
+        meta,
+        quote! {
+            let mut changed = false;
+            #(changed |= Flush::<__S>::flush_blocks(&mut self.#keys, blocks)?;)*
+            Ok(changed)
+        },
+    )
+}
+
+/// The field's type, without any `Option` added for `optional` or `nullable`
+/// fields.
+pub(super) fn field_inner_value(field: &StructField) -> TokenStream {
     let value = &field.value;
+    let generics = field.generics.as_ref().map(|g| quote!(#g));
+    quote!(#value #generics)
+}
+
+/// The field's name within the representation, i.e. its `rename` if present.
+pub(super) fn field_name(field: &StructField) -> String {
+    field
+        .rename
+        .as_ref()
+        .map(|s| s.value())
+        .unwrap_or_else(|| field.key.to_string())
+}
+
+/// Expands to whether `value`, a reference to the field's value, equals the
+/// field's `implicit` value, comparing parsed values rather than strings (so
+/// that e.g. a `Float` of `1.0` matches `"1"`).
+pub(super) fn field_is_implicit(
+    field: &StructField,
+    implicit: &LitStr,
+    value: TokenStream,
+) -> TokenStream {
+    let key_str = field.key.to_string();
+    let inner = field_inner_value(field);
+    quote! {{
+        let implicit: #inner = from_delimited(#implicit, #key_str)
+            .map_err(<S::Error as serde::ser::Error>::custom)?;
+        let implicit = to_value::<_, DefaultMultihashSize>(&implicit)
+            .map_err(<S::Error as serde::ser::Error>::custom)?;
+        to_value::<_, DefaultMultihashSize>(#value)
+            .map_err(<S::Error as serde::ser::Error>::custom)?
+            == implicit
+    }}
+}
+
+pub(super) fn field_value(field: &StructField) -> TokenStream {
+    let value = field_inner_value(field);
     if field.optional || field.nullable {
         quote!(Option<#value>)
     } else {
@@ -1,18 +1,17 @@
 use super::*;
 use crate::dev::{
-    schema::expand::{self, ExpandAdvancedRepresentation, ExpandBasicRepresentation},
+    schema::expand::{self, ExpandBasicRepresentation},
     SchemaMeta,
 };
-use proc_macro2::TokenStream;
-use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{parse_quote, Type};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
 
 impl ExpandBasicRepresentation for ListpairsStructReprDefinition {
     fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
-        let lib = meta.lib();
         let attrs = &meta.attrs;
         let vis = &meta.vis;
         let ident = &meta.name;
+        let generics = meta.generics.as_ref().map(|g| quote!(#g));
         let fields: Vec<TokenStream> = self
             .iter()
             .map(super::expand::default_field_typdef)
@@ -20,13 +19,159 @@ impl ExpandBasicRepresentation for ListpairsStructReprDefinition {
 
         quote! {
             #(#attrs)*
-            #vis struct #ident {
-                #(#fields)*
+            #vis struct #ident #generics {
+                #(#fields,)*
             }
         }
     }
     fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
-        TokenStream::default()
+        let name = &meta.name;
+        let keys: Vec<&Ident> = self.iter().map(|field| &field.key).collect();
+        let renames: Vec<String> = self.iter().map(super::expand::field_name).collect();
+
+        // absent optional fields and implicit values are omitted from the list
+        let required_len = self
+            .iter()
+            .filter(|field| !field.optional && field.implicit.is_none())
+            .count();
+        let optional_keys = self
+            .iter()
+            .filter(|field| field.optional)
+            .map(|field| &field.key);
+        let implicit_vars: Vec<Ident> = self
+            .iter()
+            .filter(|field| field.implicit.is_some())
+            .map(|field| Ident::new(&format!("emit_{}", field.key), Span::call_site()))
+            .collect();
+        let implicit_checks = self.iter().filter_map(|field| {
+            let key = &field.key;
+            let is_implicit = super::expand::field_is_implicit(
+                field,
+                field.implicit.as_ref()?,
+                quote!(&self.#key),
+            );
+            Some(quote!(!#is_implicit))
+        });
+        let serialize_fields = self.iter().zip(&renames).map(|(field, rename)| {
+            let key = &field.key;
+            if field.optional {
+                quote! {
+                    if let Some(value) = &self.#key {
+                        seq.serialize_element(&(#rename, value))?;
+                    }
+                }
+            } else if field.implicit.is_some() {
+                let var = Ident::new(&format!("emit_{}", key), Span::call_site());
+                quote! {
+                    if #var {
+                        seq.serialize_element(&(#rename, &self.#key))?;
+                    }
+                }
+            } else {
+                quote!(seq.serialize_element(&(#rename, &self.#key))?;)
+            }
+        });
+        let impl_ser = expand::impl_serialize(
+            meta,
+            quote! {
+                use serde::ser::SerializeSeq;
+                #(let #implicit_vars = #implicit_checks;)*
+                let len = #required_len
+                    #(+ self.#optional_keys.is_some() as usize)*
+                    #(+ #implicit_vars as usize)*;
+                let mut seq = serializer.serialize_seq(Some(len))?;
+                #(#serialize_fields)*
+                seq.end()
+            },
+        );
+
+        let vars: Vec<Ident> = keys
+            .iter()
+            .map(|key| Ident::new(&format!("field_{}", key), Span::call_site()))
+            .collect();
+        let values: Vec<TokenStream> = self
+            .iter()
+            .map(|field| {
+                if field.optional {
+                    super::expand::field_inner_value(field)
+                } else {
+                    super::expand::field_value(field)
+                }
+            })
+            .collect();
+        let finish_fields = self
+            .iter()
+            .zip(&vars)
+            .zip(&renames)
+            .map(|((field, var), rename)| {
+                let key = &field.key;
+                let key_str = key.to_string();
+                match (field.optional, &field.implicit) {
+                    (true, _) => quote!(let #key = #var;),
+                    (false, Some(implicit)) => quote! {
+                        let #key = match #var {
+                            Some(value) => value,
+                            None => from_delimited(#implicit, #key_str)
+                                .map_err(de::Error::custom)?,
+                        };
+                    },
+                    (false, None) => quote! {
+                        let #key = #var.ok_or_else(|| de::Error::missing_field(#rename))?;
+                    },
+                }
+            });
+        let (visitor, impl_visitor) = expand::impl_visitor(
+            meta,
+            "an IPLD struct represented as a list of [name, value] pairs",
+            quote! {
+                fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                where
+                    A: de::SeqAccess<'de>,
+                {
+                    const FIELDS: &[&str] = &[#(#renames,)*];
+                    #(let mut #vars = None;)*
+
+                    // values are buffered as `Value`s, as their type depends
+                    // on the preceding field name
+                    // NOTE: the `Value`s use the `DefaultMultihashSize`, so any
+                    // links within values must fit within it, and fields
+                    // cannot borrow from the input
+                    while let Some((key, value)) =
+                        seq.next_element::<(String, Value<DefaultMultihashSize>)>()?
+                    {
+                        match key.as_str() {
+                            #(
+                                #renames => {
+                                    if #vars.is_some() {
+                                        return Err(de::Error::duplicate_field(#renames));
+                                    }
+                                    #vars = Some(
+                                        from_value::<#values, _>(value)
+                                            .map_err(de::Error::custom)?,
+                                    );
+                                }
+                            )*
+                            _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+                        }
+                    }
+
+                    #(#finish_fields)*
+                    Ok(#name { #(#keys,)* })
+                }
+            },
+        );
+        let impl_de = expand::impl_deserialize(
+            meta,
+            quote! {
+                deserializer.deserialize_seq(#visitor)
+            },
+        );
+
+        quote! {
+            #impl_ser
+            #impl_visitor
+            #impl_de
+        }
     }
     fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
         super::expand::impl_repr(self.iter(), meta)
@@ -1,15 +1,14 @@
 use super::*;
 use crate::dev::{
-    schema::expand::{self, ExpandAdvancedRepresentation, ExpandBasicRepresentation},
+    schema::expand::{self, ExpandBasicRepresentation},
     SchemaMeta,
 };
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{parse_quote, Type};
+use quote::quote;
+use syn::Error as SynError;
 
 impl ExpandBasicRepresentation for StringjoinStructReprDefinition {
     fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
-        let lib = meta.lib();
         let attrs = &meta.attrs;
         let vis = &meta.vis;
         let ident = &meta.name;
@@ -20,14 +19,72 @@ impl ExpandBasicRepresentation for StringjoinStructReprDefinition {
 
         quote! {
             #(#attrs)*
-            #[derive(#lib::dev::serde::Deserialize, #lib::dev::serde::Serialize)]
             #vis struct #ident {
-                #(#fields)*
+                #(#fields,)*
             }
         }
     }
     fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
-        TokenStream::default()
+        // every field must be present, as an absent component is
+        // indistinguishable from an empty string
+        if let Some(field) = self.iter().find(|f| f.optional || f.nullable) {
+            return SynError::new_spanned(
+                &field.key,
+                "stringjoin-represented structs cannot have optional or nullable fields",
+            )
+            .to_compile_error();
+        }
+
+        let name = &meta.name;
+        let join = &self.join;
+        let keys: Vec<&Ident> = self.iter().map(|field| &field.key).collect();
+        let key_strs: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        let values: Vec<TokenStream> = self.iter().map(super::expand::field_value).collect();
+        let indices = 0..keys.len();
+        let len = keys.len();
+
+        let impl_ser = expand::impl_serialize(
+            meta,
+            quote! {
+                let components: Vec<String> = vec![#(
+                    to_delimited(&self.#keys, #key_strs, &[#join])
+                        .map_err(<S::Error as serde::ser::Error>::custom)?,
+                )*];
+                serializer.serialize_str(&components.join(#join))
+            },
+        );
+        let (visitor, impl_visitor) = expand::impl_visitor(
+            meta,
+            "an IPLD struct represented as a delimited string",
+            quote! {
+                fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                    let mut components = s.split(#join);
+                    #(
+                        let #keys = components
+                            .next()
+                            .ok_or_else(|| E::invalid_length(#indices, &self))?;
+                        let #keys = from_delimited::<#values>(#keys, #key_strs)
+                            .map_err(E::custom)?;
+                    )*
+                    if components.next().is_some() {
+                        return Err(E::invalid_length(#len + 1, &self));
+                    }
+                    Ok(#name { #(#keys,)* })
+                }
+            },
+        );
+        let impl_de = expand::impl_deserialize(
+            meta,
+            quote! {
+                deserializer.deserialize_str(#visitor)
+            },
+        );
+
+        quote! {
+            #impl_ser
+            #impl_visitor
+            #impl_de
+        }
     }
     fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
         super::expand::impl_repr(self.iter(), meta)
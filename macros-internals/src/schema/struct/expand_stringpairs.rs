@@ -1,15 +1,14 @@
 use super::*;
 use crate::dev::{
-    schema::expand::{self, ExpandAdvancedRepresentation, ExpandBasicRepresentation},
+    schema::expand::{self, ExpandBasicRepresentation},
     SchemaMeta,
 };
-use proc_macro2::TokenStream;
-use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{parse_quote, Type};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::Error as SynError;
 
 impl ExpandBasicRepresentation for StringpairsStructReprDefinition {
     fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
-        let lib = meta.lib();
         let attrs = &meta.attrs;
         let vis = &meta.vis;
         let ident = &meta.name;
@@ -20,14 +19,159 @@ impl ExpandBasicRepresentation for StringpairsStructReprDefinition {
 
         quote! {
             #(#attrs)*
-            #[derive(#lib::dev::Deserialize, #lib::dev::Serialize)]
             #vis struct #ident {
-                #(#fields)*
+                #(#fields,)*
             }
         }
     }
     fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
-        TokenStream::default()
+        if let Some(field) = self.iter().find(|f| f.nullable) {
+            return SynError::new_spanned(
+                &field.key,
+                "stringpairs-represented structs cannot have nullable fields",
+            )
+            .to_compile_error();
+        }
+
+        let name = &meta.name;
+        let inner = &self.inner_delim;
+        let entry = &self.entry_delim;
+        let keys: Vec<&Ident> = self.iter().map(|field| &field.key).collect();
+        let renames: Vec<String> = self.iter().map(super::expand::field_name).collect();
+
+        let serialize_fields = self.iter().map(|field| {
+            let key = &field.key;
+            let key_str = key.to_string();
+            let rename = super::expand::field_name(field);
+            let push = quote! {
+                let value = to_delimited(value, #key_str, delims)
+                    .map_err(<S::Error as serde::ser::Error>::custom)?;
+            };
+            let push = match &field.implicit {
+                // implicit values are omitted
+                Some(implicit) => {
+                    let is_implicit =
+                        super::expand::field_is_implicit(field, implicit, quote!(value));
+                    quote! {
+                        if !#is_implicit {
+                            #push
+                            entries.push(::std::format!("{}{}{}", #rename, #inner, value));
+                        }
+                    }
+                }
+                None => quote! {
+                    #push
+                    entries.push(::std::format!("{}{}{}", #rename, #inner, value));
+                },
+            };
+
+            if field.optional {
+                quote! {
+                    if let Some(value) = &self.#key {
+                        #push
+                    }
+                }
+            } else {
+                quote! {
+                    let value = &self.#key;
+                    #push
+                }
+            }
+        });
+        let impl_ser = expand::impl_serialize(
+            meta,
+            quote! {
+                let delims = &[#inner, #entry];
+                let mut entries: Vec<String> = Vec::new();
+                #(#serialize_fields)*
+                serializer.serialize_str(&entries.join(#entry))
+            },
+        );
+
+        let vars: Vec<Ident> = keys
+            .iter()
+            .map(|key| Ident::new(&format!("field_{}", key), Span::call_site()))
+            .collect();
+        let values: Vec<TokenStream> = self.iter().map(super::expand::field_inner_value).collect();
+        let key_strs: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        let finish_fields = self.iter().zip(&vars).map(|(field, var)| {
+            let key = &field.key;
+            let key_str = key.to_string();
+            let rename = super::expand::field_name(field);
+            match (field.optional, &field.implicit) {
+                (true, _) => quote!(let #key = #var;),
+                (false, Some(implicit)) => quote! {
+                    let #key = match #var {
+                        Some(value) => value,
+                        None => from_delimited(#implicit, #key_str).map_err(E::custom)?,
+                    };
+                },
+                (false, None) => quote! {
+                    let #key = #var.ok_or_else(|| E::missing_field(#rename))?;
+                },
+            }
+        });
+        let (visitor, impl_visitor) = expand::impl_visitor(
+            meta,
+            "an IPLD struct represented as a string of delimited key-value pairs",
+            quote! {
+                fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                    const FIELDS: &[&str] = &[#(#renames,)*];
+                    #(let mut #vars = None;)*
+
+                    let entries = if s.is_empty() {
+                        None
+                    } else {
+                        Some(s.split(#entry))
+                    };
+                    for pair in entries.into_iter().flatten() {
+                        let mut pair = pair.splitn(2, #inner);
+                        let (key, value) = match (pair.next(), pair.next()) {
+                            (Some(key), Some(value)) => (key, value),
+                            _ => return Err(E::custom(::std::format!(
+                                "struct entry is missing the delimiter {:?}",
+                                #inner
+                            ))),
+                        };
+                        if value.contains(#inner) {
+                            return Err(E::custom(::std::format!(
+                                "struct entry value contains the delimiter {:?}",
+                                #inner
+                            )));
+                        }
+                        match key {
+                            #(
+                                #renames => {
+                                    if #vars.is_some() {
+                                        return Err(E::duplicate_field(#renames));
+                                    }
+                                    #vars = Some(
+                                        from_delimited::<#values>(value, #key_strs)
+                                            .map_err(E::custom)?,
+                                    );
+                                }
+                            )*
+                            _ => return Err(E::unknown_field(key, FIELDS)),
+                        }
+                    }
+
+                    #(#finish_fields)*
+                    Ok(#name { #(#keys,)* })
+                }
+            },
+        );
+        let impl_de = expand::impl_deserialize(
+            meta,
+            quote! {
+                deserializer.deserialize_str(#visitor)
+            },
+        );
+
+        quote! {
+            #impl_ser
+            #impl_visitor
+            #impl_de
+        }
     }
     fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
         super::expand::impl_repr(self.iter(), meta)
@@ -36,32 +180,3 @@ impl ExpandBasicRepresentation for StringpairsStructReprDefinition {
         TokenStream::default()
     }
 }
-
-fn field_to_tokens(field: &StructField) -> TokenStream {
-    let attrs = &field.attrs;
-    let key = &field.key;
-    let value = &field.value;
-
-    let value = if field.optional {
-        quote!(Option<#value>)
-    } else {
-        quote!(#value)
-    };
-    let implicit_attr = if let Some(implicit) = &field.implicit {
-        quote!(#[serde(default)])
-    } else {
-        TokenStream::default()
-    };
-    let rename_attr = if let Some(rename) = &field.rename {
-        quote!(#[serde(rename = #rename)])
-    } else {
-        TokenStream::default()
-    };
-
-    quote! {
-        #(#attrs)*
-        #implicit_attr
-        #rename_attr
-        #key: #value
-    }
-}
@@ -1,33 +1,134 @@
 use super::*;
 use crate::dev::{
-    schema::expand::{self, ExpandAdvancedRepresentation, ExpandBasicRepresentation},
+    schema::expand::{self, ExpandBasicRepresentation},
     SchemaMeta,
 };
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{parse_quote, Type};
+use quote::quote;
+use syn::{Error as SynError, Expr, ExprArray, ExprLit, Lit};
 
 impl ExpandBasicRepresentation for TupleStructReprDefinition {
     fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
-        let lib = meta.lib();
         let attrs = &meta.attrs;
         let vis = &meta.vis;
         let ident = &meta.name;
-        let fields: Vec<TokenStream> = self
-            .iter()
-            .map(super::expand::default_field_typdef)
-            .collect();
+        let generics = meta.generics.as_ref().map(|g| quote!(#g));
+        let fields: Vec<TokenStream> = self.iter().map(field_typedef).collect();
 
         quote! {
             #(#attrs)*
-            #[derive(#lib::dev::Deserialize, #lib::dev::Serialize)]
-            #vis struct #ident {
-                #(#fields)*
+            #vis struct #ident #generics {
+                #(#fields,)*
             }
         }
     }
     fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
-        TokenStream::default()
+        let fields = match self.ordered_fields() {
+            Ok(fields) => fields,
+            Err(err) => return err.to_compile_error(),
+        };
+
+        let name = &meta.name;
+        let len = fields.len();
+        let keys: Vec<&Ident> = fields.iter().map(|field| &field.key).collect();
+
+        // trailing optional fields are omitted if absent, so shorten the list
+        // by each consecutive trailing `None`
+        let trailing_optionals = fields
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(_, field)| field.optional)
+            .map(|(idx, StructField { key, .. })| {
+                quote! {
+                    if len == #idx + 1 && self.#key.is_none() {
+                        len -= 1;
+                    }
+                }
+            });
+        let serialize_fields = fields.iter().enumerate().map(|(idx, field)| {
+            let key = &field.key;
+            let key_str = key.to_string();
+            if field.optional {
+                quote! {
+                    if #idx < len {
+                        match &self.#key {
+                            Some(value) => seq.serialize_element(value)?,
+                            None => return Err(<S::Error as serde::ser::Error>::custom(
+                                ::std::format!(
+                                    "optional field `{}` must be present when later fields are",
+                                    #key_str,
+                                ),
+                            )),
+                        }
+                    }
+                }
+            } else {
+                quote!(seq.serialize_element(&self.#key)?;)
+            }
+        });
+        let impl_ser = expand::impl_serialize(
+            meta,
+            quote! {
+                use serde::ser::SerializeSeq;
+                let mut len = #len;
+                #(#trailing_optionals)*
+                let mut seq = serializer.serialize_seq(Some(len))?;
+                #(#serialize_fields)*
+                seq.end()
+            },
+        );
+
+        let deserialize_fields = fields.iter().enumerate().map(|(idx, field)| {
+            let key = &field.key;
+            let value = super::expand::field_inner_value(field);
+            match (field.optional, field.nullable) {
+                (true, true) => quote! {
+                    let #key = seq.next_element::<Option<#value>>()?;
+                },
+                (true, false) => quote! {
+                    let #key = seq.next_element::<#value>()?;
+                },
+                (false, true) => quote! {
+                    let #key = seq
+                        .next_element::<Option<#value>>()?
+                        .ok_or_else(|| de::Error::invalid_length(#idx, &self))?;
+                },
+                (false, false) => quote! {
+                    let #key = seq
+                        .next_element::<#value>()?
+                        .ok_or_else(|| de::Error::invalid_length(#idx, &self))?;
+                },
+            }
+        });
+        let (visitor, impl_visitor) = expand::impl_visitor(
+            meta,
+            "an IPLD struct represented as a list",
+            quote! {
+                fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                where
+                    A: de::SeqAccess<'de>,
+                {
+                    #(#deserialize_fields)*
+                    if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                        return Err(de::Error::invalid_length(#len + 1, &self));
+                    }
+                    Ok(#name { #(#keys,)* })
+                }
+            },
+        );
+        let impl_de = expand::impl_deserialize(
+            meta,
+            quote! {
+                deserializer.deserialize_seq(#visitor)
+            },
+        );
+
+        quote! {
+            #impl_ser
+            #impl_visitor
+            #impl_de
+        }
     }
     fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
         super::expand::impl_repr(self.iter(), meta)
@@ -37,31 +138,91 @@ impl ExpandBasicRepresentation for TupleStructReprDefinition {
     }
 }
 
-fn field_to_tokens(field: &StructField) -> TokenStream {
+/// Defines the field, representing `optional nullable` fields as
+/// `Option<Option<T>>` so that an absent element is distinct from a null one.
+fn field_typedef(field: &StructField) -> TokenStream {
+    if !(field.optional && field.nullable) {
+        return super::expand::default_field_typdef(field);
+    }
+
     let attrs = &field.attrs;
+    let vis = &field.vis;
     let key = &field.key;
-    let value = &field.value;
-
-    let value = if field.optional {
-        quote!(Option<#value>)
-    } else {
-        quote!(#value)
-    };
-    let implicit_attr = if let Some(implicit) = &field.implicit {
-        quote!(#[serde(default)])
-    } else {
-        TokenStream::default()
-    };
-    let rename_attr = if let Some(rename) = &field.rename {
-        quote!(#[serde(rename = #rename)])
-    } else {
-        TokenStream::default()
-    };
-
+    let value = super::expand::field_inner_value(field);
     quote! {
         #(#attrs)*
-        #implicit_attr
-        #rename_attr
-        #key: #value
+        #vis #key: Option<Option<#value>>
+    }
+}
+
+impl TupleStructReprDefinition {
+    /// Returns the fields in their serialized order, i.e. as listed by
+    /// `fieldOrder` or otherwise as declared.
+    ///
+    /// Optional fields must be the last fields of the tuple, and fields cannot
+    /// be `implicit` or renamed.
+    fn ordered_fields(&self) -> Result<Vec<&StructField>, SynError> {
+        let fields = match &self.field_order {
+            None => self.iter().collect(),
+            Some(Expr::Array(ExprArray { elems, .. })) if elems.len() == self.len() => {
+                let mut fields = Vec::with_capacity(elems.len());
+                for elem in elems {
+                    let name = match elem {
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(name),
+                            ..
+                        }) => name,
+                        _ => {
+                            return Err(SynError::new_spanned(
+                                elem,
+                                "`fieldOrder` must only contain field names",
+                            ))
+                        }
+                    };
+                    let field = self
+                        .iter()
+                        .find(|field| field.key == name.value())
+                        .ok_or_else(|| SynError::new_spanned(name, "unknown field"))?;
+                    if fields.iter().any(|f: &&StructField| f.key == field.key) {
+                        return Err(SynError::new_spanned(name, "duplicate field"));
+                    }
+                    fields.push(field);
+                }
+                fields
+            }
+            Some(expr) => {
+                return Err(SynError::new_spanned(
+                    expr,
+                    "`fieldOrder` must be a list of every field name",
+                ))
+            }
+        };
+
+        for field in &fields {
+            if let Some(implicit) = &field.implicit {
+                return Err(SynError::new_spanned(
+                    implicit,
+                    "tuple-represented structs cannot have implicit fields",
+                ));
+            }
+            if let Some(rename) = &field.rename {
+                return Err(SynError::new_spanned(
+                    rename,
+                    "tuple-represented structs cannot have renamed fields",
+                ));
+            }
+        }
+
+        let first_optional = fields.iter().position(|field| field.optional);
+        if let Some(idx) = first_optional {
+            if let Some(field) = fields[idx..].iter().find(|field| !field.optional) {
+                return Err(SynError::new_spanned(
+                    &field.key,
+                    "tuple-represented structs cannot have required fields after optional fields",
+                ));
+            }
+        }
+
+        Ok(fields)
     }
 }
@@ -25,6 +25,12 @@ pub struct BasicStructReprDefinition {
     fields: StructFields,
 }
 
+/// A struct represented as a list of `[name, value]` pairs.
+///
+/// As a value's type depends on the name preceding it, values are deserialized
+/// via `Value<DefaultMultihashSize>`, so links within them must have
+/// multihashes that fit within `DefaultMultihashSize`, and fields cannot borrow
+/// from the input.
 #[derive(Debug)]
 pub struct ListpairsStructReprDefinition {
     fields: StructFields,
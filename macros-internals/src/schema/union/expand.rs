@@ -6,7 +6,7 @@ use crate::dev::{
 };
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{Ident, Type};
+use syn::{parse::Parse, Ident, Type};
 
 impl ExpandBasicRepresentation for KeyedUnionReprDefinition {
     fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
@@ -25,14 +25,19 @@ impl ExpandBasicRepresentation for KeyedUnionReprDefinition {
         }
     }
     fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
-        expand::impl_repr(
+        let repr = expand::impl_repr(
             meta,
             quote! {
                 // const KIND: _ipld::dev::Kind =
                 //     _ipld::dev::Kind::Union;
                 // const FIELDS: _ipld::dev::Fields = _ipld::dev::Fields::Keyed(&[#(#fields,)*]);
             },
-        )
+        );
+        let flush = impl_flush(self, meta);
+        quote! {
+            #repr
+            #flush
+        }
     }
     fn derive_selects(&self, meta: &SchemaMeta) -> TokenStream {
         // let name = &meta.name;
@@ -44,28 +49,72 @@ impl ExpandBasicRepresentation for KeyedUnionReprDefinition {
 
 fn field_typedef(field: &UnionField<LitStr>) -> TokenStream {
     let attrs = &field.attrs;
-    let value = &field.value;
     let key = &field.key;
-
-    let value_tokens: TokenStream = if let Some(wrapper_type) = &field.wrapper {
-        quote!(#wrapper_type<#value>)
-    } else {
-        quote!(#value)
-    };
+    let field_name = variant_name(field);
+    let value_tokens = variant_type(field);
 
     let rename_attr = quote!(#[serde(rename = #key)]);
+    quote! {
+        #(#attrs)*
+        #rename_attr
+        #field_name(#value_tokens)
+    }
+}
+
+/// Defines the union's enum, without any serde attributes.
+pub(super) fn define_enum(fields: &UnionStrFields, meta: &SchemaMeta) -> TokenStream {
+    let attrs = &meta.attrs;
+    let vis = &meta.vis;
+    let ident = &meta.name;
+    let variants = fields.iter().map(|field| {
+        let attrs = &field.attrs;
+        let variant = variant_name(field);
+        let value = variant_type(field);
+        quote! {
+            #(#attrs)*
+            #variant(#value)
+        }
+    });
+
+    quote! {
+        #(#attrs)*
+        #vis enum #ident {
+            #(#variants,)*
+        }
+    }
+}
+
+/// Implements `Flush` for unions, flushing the current member.
+pub(super) fn impl_flush(fields: &UnionStrFields, meta: &SchemaMeta) -> TokenStream {
+    let variants = fields.iter().map(variant_name);
+    expand::impl_flush(
+        meta,
+        quote! {
+            match self {
+                #(Self::#variants(value) => Flush::<__S>::flush_blocks(value, blocks),)*
+            }
+        },
+    )
+}
+
+/// The enum variant of a union member.
+pub(super) fn variant_name<T: Parse>(field: &UnionField<T>) -> Ident {
     // TODO: if the field is a link type, rename the field to avoid ambiguity,
     // TODO? but preserve the listed name?
-    let field_name: Ident = if field.linked {
-        Ident::new(&format!("{:?}Link", value), Span::call_site())
+    if field.linked {
+        Ident::new(&format!("{}Link", field.value), Span::call_site())
     } else {
         field.value.clone()
-    };
+    }
+}
 
-    quote! {
-        #(#attrs)*
-        #rename_attr
-        #field_name(#value_tokens)
+/// The type contained by the enum variant of a union member.
+pub(super) fn variant_type<T: Parse>(field: &UnionField<T>) -> TokenStream {
+    let value = &field.value;
+    if let Some(wrapper_type) = &field.wrapper {
+        quote!(#wrapper_type<#value>)
+    } else {
+        quote!(#value)
     }
 }
 
@@ -73,18 +122,28 @@ impl ExpandBasicRepresentation for UnionReprDefinition {
     fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
         match self {
             Self::Keyed(def) => def.define_type(meta),
-            // Self::Envelope(def) => def.define_type(meta),
-            // Self::Inline(def) => def.define_type(meta),
+            Self::Envelope(def) => def.define_type(meta),
+            Self::Inline(def) => def.define_type(meta),
             // Self::BytePrefix(def) => def.define_type(meta),
             // Self::Kinded(def) => def.define_type(meta),
             _ => unimplemented!(),
         }
     }
+    fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
+        match self {
+            Self::Keyed(def) => def.derive_serde(meta),
+            Self::Envelope(def) => def.derive_serde(meta),
+            Self::Inline(def) => def.derive_serde(meta),
+            // Self::BytePrefix(def) => def.derive_serde(meta),
+            // Self::Kinded(def) => def.derive_serde(meta),
+            _ => unimplemented!(),
+        }
+    }
     fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
         match self {
             Self::Keyed(def) => def.derive_repr(meta),
-            // Self::Envelope(def) => def.derive_repr(meta),
-            // Self::Inline(def) => def.derive_repr(meta),
+            Self::Envelope(def) => def.derive_repr(meta),
+            Self::Inline(def) => def.derive_repr(meta),
             // Self::BytePrefix(def) => def.derive_repr(meta),
             // Self::Kinded(def) => def.derive_repr(meta),
             _ => unimplemented!(),
@@ -93,8 +152,8 @@ impl ExpandBasicRepresentation for UnionReprDefinition {
     fn derive_selects(&self, meta: &SchemaMeta) -> TokenStream {
         match self {
             Self::Keyed(def) => def.derive_selects(meta),
-            // Self::Envelope(def) => def.derive_selects(meta),
-            // Self::Inline(def) => def.derive_selects(meta),
+            Self::Envelope(def) => def.derive_selects(meta),
+            Self::Inline(def) => def.derive_selects(meta),
             // Self::BytePrefix(def) => def.derive_selects(meta),
             // Self::Kinded(def) => def.derive_selects(meta),
             _ => unimplemented!(),
@@ -1,54 +1,127 @@
 use super::*;
-use crate::dev::{schema::expand::ExpandBasicRepresentation, SchemaMeta};
+use crate::dev::{
+    schema::expand::{self, ExpandBasicRepresentation},
+    SchemaMeta,
+};
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens, TokenStreamExt};
+use quote::quote;
 
 impl ExpandBasicRepresentation for EnvelopeUnionReprDefinition {
     fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
-        let lib = meta.lib();
-        let attrs = &meta.attrs;
-        let vis = &meta.vis;
-        let ident = &meta.name;
-        let fields: Vec<TokenStream> = self.iter().map(field_typedef).collect();
+        super::expand::define_enum(self, meta)
+    }
+    fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
+        let name = &meta.name;
+        let dk = &self.discriminant_key;
+        let ck = &self.content_key;
+        let keys: Vec<&LitStr> = self.iter().map(|field| &field.key).collect();
+        let variants: Vec<Ident> = self.iter().map(super::expand::variant_name).collect();
+        let values: Vec<TokenStream> = self.iter().map(super::expand::variant_type).collect();
+
+        let impl_ser = expand::impl_serialize(
+            meta,
+            quote! {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(2))?;
+                match self {
+                    #(
+                        Self::#variants(value) => {
+                            map.serialize_entry(#dk, #keys)?;
+                            map.serialize_entry(#ck, value)?;
+                        }
+                    )*
+                }
+                map.end()
+            },
+        );
+        let (visitor, impl_visitor) = expand::impl_visitor(
+            meta,
+            "an IPLD envelope union",
+            quote! {
+                fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                where
+                    A: de::MapAccess<'de>,
+                {
+                    const FIELDS: &[&str] = &[#dk, #ck];
+                    const VARIANTS: &[&str] = &[#(#keys,)*];
+
+                    // the content is buffered as a `Value` if it precedes
+                    // the discriminant
+                    // NOTE: the `Value` uses the `DefaultMultihashSize`, so any
+                    // links within buffered content must fit within it, and
+                    // buffered content cannot borrow from the input
+                    let mut tag: Option<String> = None;
+                    let mut content: Option<#name> = None;
+                    let mut buffered: Option<Value<DefaultMultihashSize>> = None;
+                    while let Some(key) = map.next_key::<String>()? {
+                        match key.as_str() {
+                            #dk => {
+                                if tag.is_some() {
+                                    return Err(de::Error::duplicate_field(#dk));
+                                }
+                                tag = Some(map.next_value()?);
+                            }
+                            #ck => {
+                                if content.is_some() || buffered.is_some() {
+                                    return Err(de::Error::duplicate_field(#ck));
+                                }
+                                match tag.as_deref() {
+                                    #(
+                                        Some(#keys) => {
+                                            let value = map.next_value::<#values>()?;
+                                            content = Some(#name::#variants(value));
+                                        }
+                                    )*
+                                    Some(tag) => {
+                                        return Err(de::Error::unknown_variant(tag, VARIANTS));
+                                    }
+                                    None => buffered = Some(map.next_value()?),
+                                }
+                            }
+                            _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+                        }
+                    }
+
+                    match (tag, content, buffered) {
+                        (_, Some(content), _) => Ok(content),
+                        (Some(tag), None, Some(value)) => match tag.as_str() {
+                            #(
+                                #keys => {
+                                    let value = from_value::<#values, _>(value)
+                                        .map_err(de::Error::custom)?;
+                                    Ok(#name::#variants(value))
+                                }
+                            )*
+                            _ => Err(de::Error::unknown_variant(&tag, VARIANTS)),
+                        },
+                        (None, _, _) => Err(de::Error::missing_field(#dk)),
+                        (Some(_), None, None) => Err(de::Error::missing_field(#ck)),
+                    }
+                }
+            },
+        );
+        let impl_de = expand::impl_deserialize(
+            meta,
+            quote! {
+                deserializer.deserialize_map(#visitor)
+            },
+        );
 
         quote! {
-            #(#attrs)*
-            #[derive(#lib::dev::serde::Deserialize, #lib::dev::serde::Serialize)]
-            #vis enum #ident {
-                #(#fields)*
-            }
+            #impl_ser
+            #impl_visitor
+            #impl_de
         }
     }
-    fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
-        TokenStream::default()
-    }
     fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
-        // impl_repr(self.iter(), meta)
-        TokenStream::default()
+        let repr = expand::impl_repr(meta, TokenStream::default());
+        let flush = super::expand::impl_flush(self, meta);
+        quote! {
+            #repr
+            #flush
+        }
     }
     fn derive_selects(&self, meta: &SchemaMeta) -> TokenStream {
         TokenStream::default()
     }
 }
-
-fn field_typedef(field: &UnionField<LitStr>) -> TokenStream {
-    let attrs = &field.attrs;
-    let key = &field.key;
-
-    // let implicit_attr = if let Some(implicit) = &field.implicit {
-    //     quote!(#[serde(default)])
-    // } else {
-    //     TokenStream::default()
-    // };
-
-    // let rename_attr = if let Some(rename) = &field.rename {
-    //     quote!(#[serde(rename = #rename)])
-    // } else {
-    //     TokenStream::default()
-    // };
-
-    quote! {
-        #(#attrs)*
-        #key
-    }
-}
@@ -1,54 +1,125 @@
 use super::*;
-use crate::dev::{schema::expand::ExpandBasicRepresentation, SchemaMeta};
+use crate::dev::{
+    schema::expand::{self, ExpandBasicRepresentation},
+    SchemaMeta,
+};
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens, TokenStreamExt};
+use quote::quote;
 
 impl ExpandBasicRepresentation for InlineUnionReprDefinition {
     fn define_type(&self, meta: &SchemaMeta) -> TokenStream {
-        let lib = meta.lib();
-        let attrs = &meta.attrs;
-        let vis = &meta.vis;
-        let ident = &meta.name;
-        let fields: Vec<TokenStream> = self.iter().map(field_typedef).collect();
+        super::expand::define_enum(self, meta)
+    }
+    fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
+        let name = &meta.name;
+        let dk = &self.discriminant_key;
+        let keys: Vec<&LitStr> = self.iter().map(|field| &field.key).collect();
+        let variants: Vec<Ident> = self.iter().map(super::expand::variant_name).collect();
+        let values: Vec<TokenStream> = self.iter().map(super::expand::variant_type).collect();
+
+        // members are converted to and from `Value`s to merge in or extract
+        // the discriminant, wherever it may be in the member's map
+        // NOTE: the `Value`s use the `DefaultMultihashSize`, so any links within
+        // members must have multihashes that fit within it, and members cannot
+        // borrow from the input
+        let impl_ser = expand::impl_serialize(
+            meta,
+            quote! {
+                let (tag, value) = match self {
+                    #(
+                        Self::#variants(value) => (
+                            #keys,
+                            to_value::<_, DefaultMultihashSize>(value)
+                                .map_err(<S::Error as serde::ser::Error>::custom)?,
+                        ),
+                    )*
+                };
+
+                match value {
+                    Value::Map(mut map) => {
+                        if map.insert(#dk.into(), Value::String(tag.into())).is_some() {
+                            return Err(<S::Error as serde::ser::Error>::custom(::std::format!(
+                                "inline union member `{}` already contains the key {:?}",
+                                tag,
+                                #dk,
+                            )));
+                        }
+                        Value::<DefaultMultihashSize>::Map(map).serialize(serializer)
+                    }
+                    value => Err(<S::Error as serde::ser::Error>::custom(::std::format!(
+                        "inline union member `{}` must be represented as a map, found {:?}",
+                        tag,
+                        value.kind(),
+                    ))),
+                }
+            },
+        );
+        let (visitor, impl_visitor) = expand::impl_visitor(
+            meta,
+            "an IPLD inline union",
+            quote! {
+                fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                where
+                    A: de::MapAccess<'de>,
+                {
+                    const VARIANTS: &[&str] = &[#(#keys,)*];
+
+                    let mut tag: Option<String> = None;
+                    let mut member = ::std::collections::BTreeMap::new();
+                    while let Some(key) = map.next_key::<String>()? {
+                        if key == #dk {
+                            if tag.is_some() {
+                                return Err(de::Error::duplicate_field(#dk));
+                            }
+                            tag = Some(map.next_value()?);
+                        } else {
+                            let value: Value<DefaultMultihashSize> = map.next_value()?;
+                            if member.contains_key(&key) {
+                                return Err(de::Error::custom(::std::format!(
+                                    "duplicate key {:?}",
+                                    key,
+                                )));
+                            }
+                            member.insert(key, value);
+                        }
+                    }
+
+                    let tag = tag.ok_or_else(|| de::Error::missing_field(#dk))?;
+                    match tag.as_str() {
+                        #(
+                            #keys => {
+                                let value = from_value::<#values, _>(Value::Map(member))
+                                    .map_err(de::Error::custom)?;
+                                Ok(#name::#variants(value))
+                            }
+                        )*
+                        _ => Err(de::Error::unknown_variant(&tag, VARIANTS)),
+                    }
+                }
+            },
+        );
+        let impl_de = expand::impl_deserialize(
+            meta,
+            quote! {
+                deserializer.deserialize_map(#visitor)
+            },
+        );
 
         quote! {
-            #(#attrs)*
-            #[derive(#lib::dev::serde::Deserialize, #lib::dev::serde::Serialize)]
-            #vis enum #ident {
-                #(#fields)*
-            }
+            #impl_ser
+            #impl_visitor
+            #impl_de
         }
     }
-    fn derive_serde(&self, meta: &SchemaMeta) -> TokenStream {
-        TokenStream::default()
-    }
     fn derive_repr(&self, meta: &SchemaMeta) -> TokenStream {
-        // impl_repr(self.iter(), meta)
-        TokenStream::default()
+        let repr = expand::impl_repr(meta, TokenStream::default());
+        let flush = super::expand::impl_flush(self, meta);
+        quote! {
+            #repr
+            #flush
+        }
     }
     fn derive_selects(&self, meta: &SchemaMeta) -> TokenStream {
         TokenStream::default()
     }
 }
-
-fn field_typedef(field: &UnionField<LitStr>) -> TokenStream {
-    let attrs = &field.attrs;
-    let key = &field.key;
-
-    // let implicit_attr = if let Some(implicit) = &field.implicit {
-    //     quote!(#[serde(default)])
-    // } else {
-    //     TokenStream::default()
-    // };
-
-    // let rename_attr = if let Some(rename) = &field.rename {
-    //     quote!(#[serde(rename = #rename)])
-    // } else {
-    //     TokenStream::default()
-    // };
-
-    quote! {
-        #(#attrs)*
-        #key
-    }
-}
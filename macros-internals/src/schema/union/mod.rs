@@ -23,12 +23,22 @@ pub enum UnionReprDefinition {
 pub struct KeyedUnionReprDefinition {
     fields: UnionStrFields,
 }
+/// A union represented as a map of a discriminant and the member's content.
+///
+/// Content preceding the discriminant is deserialized via
+/// `Value<DefaultMultihashSize>`, so links within it must have multihashes that
+/// fit within `DefaultMultihashSize`, and members cannot borrow from the input.
 #[derive(Debug)]
 pub struct EnvelopeUnionReprDefinition {
     fields: UnionStrFields,
     discriminant_key: LitStr,
     content_key: LitStr,
 }
+/// A union represented as the member's map, with the discriminant inlined.
+///
+/// Members are serialized and deserialized via `Value<DefaultMultihashSize>`,
+/// so links within them must have multihashes that fit within
+/// `DefaultMultihashSize`, and members cannot borrow from the input.
 #[derive(Debug)]
 pub struct InlineUnionReprDefinition {
     fields: UnionStrFields,
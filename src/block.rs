@@ -0,0 +1,162 @@
+//! IPLD blocks.
+
+use crate::dev::*;
+use cid::Error as CidError;
+use std::convert::TryFrom;
+
+/// An IPLD block, i.e. some encoded bytes and the `CidGeneric` that identifies
+/// them.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Block<S = DefaultMultihashSize>
+where
+    S: MultihashSize,
+{
+    cid: CidGeneric<S>,
+    bytes: bytes::Bytes,
+}
+
+impl<S: MultihashSize> Block<S> {
+    /// Creates a new `Block` from a `CidGeneric` and its bytes, verifying that
+    /// the bytes match the CID.
+    #[inline]
+    pub fn new(cid: CidGeneric<S>, bytes: impl Into<bytes::Bytes>) -> Result<Self, Error> {
+        let block = Self::new_unchecked(cid, bytes);
+        block.verify()?;
+        Ok(block)
+    }
+
+    /// Creates a new `Block` from a `CidGeneric` and its bytes, without
+    /// verifying that the bytes match the CID.
+    #[inline]
+    pub fn new_unchecked(cid: CidGeneric<S>, bytes: impl Into<bytes::Bytes>) -> Self {
+        Self {
+            cid,
+            bytes: bytes.into(),
+        }
+    }
+
+    /// Encodes a dag with the codec `C`, hashing the encoded bytes with the
+    /// multihash `mh_code` to create a CIDv1.
+    pub fn encode<C, T>(dag: &T, mh_code: u64) -> Result<Self, Error>
+    where
+        C: Codec,
+        T: Representation + Serialize,
+    {
+        let mut bytes = Vec::new();
+        C::write(dag, &mut bytes)?;
+
+        let hash = hash::<S>(mh_code, &bytes)?;
+        Ok(Self::new_unchecked(
+            CidGeneric::new_v1(C::CODE, hash),
+            bytes,
+        ))
+    }
+
+    /// Encodes a dag with the codec of the multicodec `code`, hashing the
+    /// encoded bytes with the multihash `mh_code` to create a CIDv1.
+    pub fn encode_any<T>(code: u64, dag: &T, mh_code: u64) -> Result<Self, Error>
+    where
+        T: Representation + Serialize + 'static,
+    {
+        let bytes = codecs::encode_any(code, dag)?;
+        let hash = hash::<S>(mh_code, &bytes)?;
+        Ok(Self::new_unchecked(CidGeneric::new_v1(code, hash), bytes))
+    }
+
+    /// The block's `CidGeneric`.
+    #[inline]
+    pub fn cid(&self) -> &CidGeneric<S> {
+        &self.cid
+    }
+
+    /// The block's encoded bytes.
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Consumes the block, returning its `CidGeneric` and bytes.
+    #[inline]
+    pub fn into_inner(self) -> (CidGeneric<S>, bytes::Bytes) {
+        (self.cid, self.bytes)
+    }
+
+    /// Re-hashes the block's bytes, checking that they match its `CidGeneric`.
+    pub fn verify(&self) -> Result<(), Error> {
+        let expected = self.cid.hash();
+        let actual = hash::<S>(expected.code(), &self.bytes)?;
+        if actual.digest() == expected.digest() {
+            Ok(())
+        } else {
+            Err(Error::InvalidBlock(self.cid.to_string()))
+        }
+    }
+
+    /// Decodes the block's bytes into a dag, using the codec of its
+    /// `CidGeneric`.
+    #[inline]
+    pub fn decode<T>(&self) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned + 'static,
+    {
+        codecs::decode_any(self.cid.codec(), &self.bytes)
+    }
+}
+
+/// Hashes bytes with the multihash `mh_code`, or embeds them if `mh_code` is
+/// the identity multihash.
+fn hash<S: MultihashSize>(
+    mh_code: u64,
+    bytes: &[u8],
+) -> Result<multihash::MultihashGeneric<S>, Error> {
+    let digest = match mh_code {
+        codecs::IDENTITY => bytes.to_vec(),
+        _ => DefaultMultihash::try_from(mh_code)
+            .map_err(CidError::from)?
+            .digest(bytes)
+            .digest()
+            .to_vec(),
+    };
+    multihash::MultihashGeneric::wrap(mh_code, &digest).map_err(|e| CidError::from(e).into())
+}
+
+#[cfg(all(test, feature = "dag-cbor"))]
+mod tests {
+    use super::*;
+
+    const SHA2_256: u64 = 0x12;
+
+    #[test]
+    fn test_encode() {
+        let dag = String::from("hello");
+        let block = Block::<DefaultMultihashSize>::encode::<DagCbor, _>(&dag, SHA2_256).unwrap();
+
+        assert_eq!(block.cid().codec(), DagCbor::CODE);
+        assert_eq!(block.cid().hash().code(), SHA2_256);
+        assert_eq!(block.bytes(), &[0x65, b'h', b'e', b'l', b'l', b'o'][..]);
+        assert!(block.verify().is_ok());
+        assert_eq!(block.decode::<String>().unwrap(), dag);
+
+        let (cid, bytes) = block.into_inner();
+        assert!(Block::new(cid, bytes).is_ok());
+    }
+
+    #[test]
+    fn test_identity() {
+        let block = Block::<DefaultMultihashSize>::encode::<DagCbor, _>(&true, IDENTITY).unwrap();
+        assert_eq!(identity_bytes(block.cid()), Some(&[0xf5][..]));
+        assert!(block.verify().is_ok());
+        assert!(block.decode::<bool>().unwrap());
+    }
+
+    #[test]
+    fn test_verify() {
+        let block = Block::<DefaultMultihashSize>::encode::<DagCbor, _>(&true, SHA2_256).unwrap();
+        let (cid, _) = block.into_inner();
+
+        let err = Block::new(cid.clone(), vec![0xf4]).unwrap_err();
+        assert!(matches!(err, Error::InvalidBlock(_)));
+        let block = Block::new_unchecked(cid, vec![0xf4]);
+        assert!(block.verify().is_err());
+    }
+}
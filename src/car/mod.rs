@@ -0,0 +1,98 @@
+//! [CAR](https://github.com/ipld/specs/blob/master/block-layer/content-addressable-archives.md)
+//! (Content Addressable aRchive) readers and writers.
+
+mod v1;
+mod v2;
+
+pub use v1::{CarHeader, CarReader, CarWriter};
+pub use v2::{
+    CarBlockStore, CarV2Header, CarV2Writer, Characteristics, Index, IndexFormat, HEADER_LEN,
+    PRAGMA,
+};
+
+use crate::dev::*;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::io::Cursor;
+
+/// The maximum length of a CAR header or section (i.e. a CID and block).
+pub const MAX_SECTION_LEN: u64 = 32 * 1024 * 1024;
+
+fn invalid(msg: impl std::fmt::Display) -> Error {
+    Error::InvalidCar(msg.to_string())
+}
+
+fn io_err(err: std::io::Error) -> Error {
+    Error::Io(err)
+}
+
+/// Reads an unsigned varint, returning `None` if the reader is already at EOF.
+async fn read_varint<R>(reader: &mut R) -> Result<Option<u64>, Error>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let mut n = 0u64;
+    for (i, shift) in (0..64).step_by(7).enumerate() {
+        let mut byte = [0u8];
+        if reader.read(&mut byte).await.map_err(io_err)? == 0 {
+            return match i {
+                0 => Ok(None),
+                _ => Err(invalid("unexpected end of varint")),
+            };
+        }
+
+        n |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(n));
+        }
+    }
+    Err(invalid("varint overflow"))
+}
+
+/// Reads a varint-length-prefixed section, returning `None` if the reader is
+/// already at EOF.
+async fn read_section<R>(reader: &mut R) -> Result<Option<Vec<u8>>, Error>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let len = match read_varint(reader).await? {
+        None => return Ok(None),
+        Some(len) if len > MAX_SECTION_LEN => {
+            return Err(invalid(format!("section length {} is too large", len)))
+        }
+        Some(len) => len as usize,
+    };
+
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await.map_err(io_err)?;
+    Ok(Some(bytes))
+}
+
+/// Parses a section into a `Block`, verifying the block against its CID.
+fn read_block(section: &[u8]) -> Result<Block, Error> {
+    let mut cursor = Cursor::new(section);
+    let cid = Cid::read_bytes(&mut cursor)?;
+    let bytes = section[cursor.position() as usize..].to_vec();
+    Block::new(cid, bytes)
+}
+
+/// Writes a varint-length-prefixed section, returning the number of bytes
+/// written.
+async fn write_section<W>(writer: &mut W, parts: &[&[u8]]) -> Result<u64, Error>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    let len = parts.iter().map(|part| part.len() as u64).sum::<u64>();
+    let mut bytes = Vec::new();
+    let mut n = len;
+    while n >= 0x80 {
+        bytes.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+
+    writer.write_all(&bytes).await.map_err(io_err)?;
+    for part in parts {
+        writer.write_all(part).await.map_err(io_err)?;
+    }
+    Ok(bytes.len() as u64 + len)
+}
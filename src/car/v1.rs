@@ -0,0 +1,194 @@
+//! CARv1.
+
+use super::{invalid, io_err, read_block, read_section, write_section};
+use crate::dev::*;
+use futures::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    pin_mut, stream,
+};
+
+schema! {
+    /// The header of a CARv1.
+    #[ipld_attr(internal)]
+    #[derive(Debug, PartialEq)]
+    pub type CarHeader struct {
+        pub version u64,
+        pub roots Vec<Link<Null>>,
+    };
+}
+
+impl CarHeader {
+    /// Creates a new CARv1 header from a set of root CIDs.
+    #[inline]
+    pub fn new(roots: impl IntoIterator<Item = Cid>) -> Self {
+        Self {
+            version: 1,
+            roots: roots.into_iter().map(Link::from).collect(),
+        }
+    }
+
+    /// The CIDs of the CAR's root blocks.
+    #[inline]
+    pub fn roots(&self) -> impl Iterator<Item = &Cid> {
+        self.roots.iter().map(Link::cid)
+    }
+}
+
+/// A streaming CARv1 reader, that verifies each block against its CID.
+#[derive(Debug)]
+pub struct CarReader<R> {
+    reader: R,
+    header: CarHeader,
+}
+
+impl<R> CarReader<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    /// Creates a new `CarReader`, reading the CAR header from the reader.
+    pub async fn new(mut reader: R) -> Result<Self, Error> {
+        let header_bytes = read_section(&mut reader)
+            .await?
+            .ok_or_else(|| invalid("missing header"))?;
+        let header: CarHeader = DagCbor::decode(&header_bytes)?;
+        if header.version != 1 {
+            return Err(invalid(format!("unsupported version {}", header.version)));
+        }
+
+        Ok(Self { reader, header })
+    }
+
+    /// The CAR header.
+    #[inline]
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// Reads the next block's CID and bytes, or `None` at the end of the CAR.
+    pub async fn next_block(&mut self) -> Result<Option<(Cid, bytes::Bytes)>, Error> {
+        match read_section(&mut self.reader).await? {
+            None => Ok(None),
+            Some(section) => Ok(Some(read_block(&section)?.into_inner())),
+        }
+    }
+
+    /// Converts the reader into a `Stream` of CIDs and block bytes.
+    pub fn into_stream(self) -> impl Stream<Item = Result<(Cid, bytes::Bytes), Error>> {
+        stream::try_unfold(self, |mut reader| async move {
+            Ok(reader.next_block().await?.map(|block| (block, reader)))
+        })
+    }
+}
+
+/// A streaming CARv1 writer, that verifies each block against its CID.
+#[derive(Debug)]
+pub struct CarWriter<W> {
+    writer: W,
+}
+
+impl<W> CarWriter<W>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    /// Creates a new `CarWriter`, writing a CAR header with the given roots.
+    pub async fn new(roots: impl IntoIterator<Item = Cid>, mut writer: W) -> Result<Self, Error> {
+        let mut header_bytes = Vec::new();
+        DagCbor::write(&CarHeader::new(roots), &mut header_bytes)?;
+        write_section(&mut writer, &[&header_bytes]).await?;
+        Ok(Self { writer })
+    }
+
+    /// Verifies and writes a block.
+    pub async fn write_block(&mut self, block: &Block) -> Result<(), Error> {
+        block.verify()?;
+        let cid_bytes = block.cid().to_bytes();
+        write_section(&mut self.writer, &[&cid_bytes, block.bytes()]).await?;
+        Ok(())
+    }
+
+    /// Verifies and writes a `Stream` of blocks.
+    pub async fn write_stream<St>(&mut self, blocks: St) -> Result<(), Error>
+    where
+        St: Stream<Item = Result<Block, Error>> + Send,
+    {
+        pin_mut!(blocks);
+        while let Some(block) = blocks.next().await {
+            self.write_block(&block?).await?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the writer, returning it.
+    pub async fn finish(mut self) -> Result<W, Error> {
+        self.writer.flush().await.map_err(io_err)?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, io::Cursor, TryStreamExt};
+
+    const SHA2_256: u64 = 0x12;
+
+    fn blocks() -> Vec<Block> {
+        vec![
+            Block::encode::<DagCbor, _>(&String::from("hello"), SHA2_256).unwrap(),
+            Block::encode::<DagCbor, _>(&true, SHA2_256).unwrap(),
+        ]
+    }
+
+    fn write_car(blocks: Vec<Block>) -> Vec<u8> {
+        block_on(async {
+            let roots = vec![blocks[0].cid().clone()];
+            let mut writer = CarWriter::new(roots, Vec::new()).await.unwrap();
+            writer
+                .write_stream(stream::iter(blocks.into_iter().map(Ok)))
+                .await
+                .unwrap();
+            writer.finish().await.unwrap()
+        })
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let bytes = write_car(blocks());
+
+        block_on(async {
+            let reader = CarReader::new(Cursor::new(bytes)).await.unwrap();
+            assert_eq!(reader.header().version, 1);
+            assert_eq!(
+                reader.header().roots().collect::<Vec<_>>(),
+                vec![blocks()[0].cid()]
+            );
+
+            let read: Vec<Block> = reader
+                .into_stream()
+                .map_ok(|(cid, bytes)| Block::new_unchecked(cid, bytes))
+                .try_collect()
+                .await
+                .unwrap();
+            assert_eq!(read, blocks());
+        });
+    }
+
+    #[test]
+    fn test_verify() {
+        // writing an invalid block
+        block_on(async {
+            let block = Block::new_unchecked(blocks()[0].cid().clone(), vec![0xf5]);
+            let mut writer = CarWriter::new(vec![], Vec::new()).await.unwrap();
+            assert!(writer.write_block(&block).await.is_err());
+        });
+
+        // reading an invalid block
+        let mut bytes = write_car(blocks());
+        *bytes.last_mut().unwrap() ^= 0xff;
+        block_on(async {
+            let mut reader = CarReader::new(Cursor::new(bytes)).await.unwrap();
+            assert!(reader.next_block().await.unwrap().is_some());
+            assert!(reader.next_block().await.is_err());
+        });
+    }
+}
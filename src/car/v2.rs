@@ -0,0 +1,642 @@
+//! CARv2.
+
+use super::{invalid, io_err, read_block, read_section, write_section, CarHeader, CarReader};
+use crate::{dev::*, store::BlockStore};
+use async_trait::async_trait;
+use blocking::{unblock, Unblock};
+use futures::{
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt},
+    pin_mut,
+};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fs::File,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+};
+
+/// The bytes preceding every CARv2, i.e. a CARv1 header of `{"version": 2}`.
+pub const PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, 0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e, 0x02,
+];
+
+/// The length of a CARv2 header.
+pub const HEADER_LEN: usize = 40;
+
+/// The 128-bit bitfield of features of a CARv2.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Characteristics(u128);
+
+impl Characteristics {
+    /// Whether or not the index contains every block in the CAR, including
+    /// those with identity CIDs.
+    pub const FULLY_INDEXED: Self = Self(1 << (64 + 7));
+
+    /// Whether or not all of the given characteristics are set.
+    #[inline]
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Sets the given characteristics.
+    #[inline]
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    /// Reads the characteristics as two little-endian `u64`s, the most
+    /// significant half first.
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let hi = u64::from_le_bytes(le_bytes(&bytes[..8]));
+        let lo = u64::from_le_bytes(le_bytes(&bytes[8..16]));
+        Self(u128::from(hi) << 64 | u128::from(lo))
+    }
+
+    fn to_bytes(self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&((self.0 >> 64) as u64).to_le_bytes());
+        bytes[8..].copy_from_slice(&(self.0 as u64).to_le_bytes());
+        bytes
+    }
+}
+
+/// The header of a CARv2, locating its CARv1 data payload and index.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CarV2Header {
+    /// The features of the CAR.
+    pub characteristics: Characteristics,
+    /// The offset of the CARv1 data payload from the start of the CAR.
+    pub data_offset: u64,
+    /// The length of the CARv1 data payload.
+    pub data_size: u64,
+    /// The offset of the index from the start of the CAR, or zero if the CAR
+    /// has no index.
+    pub index_offset: u64,
+}
+
+impl CarV2Header {
+    /// Reads a header from its byte representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != HEADER_LEN {
+            return Err(invalid(format!("header length {}", bytes.len())));
+        }
+
+        Ok(Self {
+            characteristics: Characteristics::from_bytes(&bytes[..16]),
+            data_offset: u64::from_le_bytes(le_bytes(&bytes[16..24])),
+            data_size: u64::from_le_bytes(le_bytes(&bytes[24..32])),
+            index_offset: u64::from_le_bytes(le_bytes(&bytes[32..40])),
+        })
+    }
+
+    /// The header's byte representation.
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[..16].copy_from_slice(&self.characteristics.to_bytes());
+        bytes[16..24].copy_from_slice(&self.data_offset.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.data_size.to_le_bytes());
+        bytes[32..].copy_from_slice(&self.index_offset.to_le_bytes());
+        bytes
+    }
+}
+
+/// The formats of a CARv2 index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IndexFormat {
+    /// Sorted buckets of multihash digests and their offsets.
+    IndexSorted,
+    /// `IndexSorted` buckets, grouped by multihash code.
+    MultihashIndexSorted,
+}
+
+impl Into<u64> for IndexFormat {
+    fn into(self) -> u64 {
+        match self {
+            Self::IndexSorted => 0x0400,
+            Self::MultihashIndexSorted => 0x0401,
+        }
+    }
+}
+
+impl TryFrom<u64> for IndexFormat {
+    type Error = Error;
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        match code {
+            0x0400 => Ok(Self::IndexSorted),
+            0x0401 => Ok(Self::MultihashIndexSorted),
+            _ => Err(invalid(format!("unknown index format {}", code))),
+        }
+    }
+}
+
+/// An index of the offsets of each block within a CARv1 data payload, keyed by
+/// the multihash code (if known) and digest of their CIDs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Index {
+    entries: BTreeMap<Option<u64>, BTreeMap<Vec<u8>, Vec<u64>>>,
+}
+
+impl Index {
+    /// Adds the offset of a block to the index.
+    pub fn insert<S: MultihashSize>(&mut self, cid: &CidGeneric<S>, offset: u64) {
+        self.entries
+            .entry(Some(cid.hash().code()))
+            .or_default()
+            .entry(cid.hash().digest().to_vec())
+            .or_default()
+            .push(offset);
+    }
+
+    /// The offsets of the blocks whose multihash digest matches that of the
+    /// given CID.
+    pub fn offsets<'a, S: MultihashSize>(
+        &'a self,
+        cid: &'a CidGeneric<S>,
+    ) -> impl Iterator<Item = u64> + 'a {
+        let digest = cid.hash().digest();
+        vec![Some(cid.hash().code()), None]
+            .into_iter()
+            .filter_map(move |code| self.entries.get(&code)?.get(digest))
+            .flatten()
+            .copied()
+    }
+
+    /// Reads an index, prefixed by its format's multicodec code.
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
+        let mut index = Self::default();
+        match IndexFormat::try_from(take_varint(&mut bytes)?)? {
+            IndexFormat::IndexSorted => {
+                read_buckets(&mut bytes, index.entries.entry(None).or_default())?;
+            }
+            IndexFormat::MultihashIndexSorted => {
+                for _ in 0..take_u32(&mut bytes)? {
+                    let code = u64::from_le_bytes(le_bytes(take(&mut bytes, 8)?));
+                    read_buckets(&mut bytes, index.entries.entry(Some(code)).or_default())?;
+                }
+            }
+        }
+
+        if !bytes.is_empty() {
+            return Err(invalid("trailing bytes after index"));
+        }
+        Ok(index)
+    }
+
+    /// Writes the index in the given format, prefixed by the format's
+    /// multicodec code.
+    ///
+    /// Fails if writing a `MultihashIndexSorted` index from an `IndexSorted`
+    /// index, as the multihash codes of its entries are unknown.
+    pub fn to_bytes(&self, format: IndexFormat) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        let mut code: u64 = format.into();
+        while code >= 0x80 {
+            bytes.push(code as u8 | 0x80);
+            code >>= 7;
+        }
+        bytes.push(code as u8);
+
+        match format {
+            IndexFormat::IndexSorted => {
+                write_buckets(self.entries.values().flatten(), &mut bytes);
+            }
+            IndexFormat::MultihashIndexSorted => {
+                if self.entries.contains_key(&None) {
+                    return Err(invalid("index is missing multihash codes"));
+                }
+
+                bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+                for (code, digests) in self.entries.iter() {
+                    let code = code.expect("all entries have multihash codes");
+                    bytes.extend_from_slice(&code.to_le_bytes());
+                    write_buckets(digests.iter(), &mut bytes);
+                }
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// Reads `IndexSorted` buckets of fixed-width digest and offset entries.
+fn read_buckets(bytes: &mut &[u8], digests: &mut BTreeMap<Vec<u8>, Vec<u64>>) -> Result<(), Error> {
+    for _ in 0..take_u32(bytes)? {
+        let width = take_u32(bytes)? as usize;
+        let len = u64::from_le_bytes(le_bytes(take(bytes, 8)?)) as usize;
+        if width < 8 || len % width != 0 {
+            return Err(invalid(format!("invalid index bucket width {}", width)));
+        }
+
+        for entry in take(bytes, len)?.chunks(width) {
+            let (digest, offset) = entry.split_at(width - 8);
+            digests
+                .entry(digest.to_vec())
+                .or_default()
+                .push(u64::from_le_bytes(le_bytes(offset)));
+        }
+    }
+    Ok(())
+}
+
+/// Writes `IndexSorted` buckets, grouped and sorted by width, then digest.
+fn write_buckets<'a>(
+    digests: impl Iterator<Item = (&'a Vec<u8>, &'a Vec<u64>)>,
+    bytes: &mut Vec<u8>,
+) {
+    let mut buckets: BTreeMap<usize, Vec<(&[u8], u64)>> = BTreeMap::new();
+    for (digest, offsets) in digests {
+        let bucket = buckets.entry(digest.len() + 8).or_default();
+        bucket.extend(offsets.iter().map(|offset| (digest.as_slice(), *offset)));
+    }
+
+    bytes.extend_from_slice(&(buckets.len() as u32).to_le_bytes());
+    for (width, mut entries) in buckets {
+        entries.sort();
+        bytes.extend_from_slice(&(width as u32).to_le_bytes());
+        bytes.extend_from_slice(&((entries.len() * width) as u64).to_le_bytes());
+        for (digest, offset) in entries {
+            bytes.extend_from_slice(digest);
+            bytes.extend_from_slice(&offset.to_le_bytes());
+        }
+    }
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if bytes.len() < len {
+        return Err(invalid("unexpected end of index"));
+    }
+
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn take_u32(bytes: &mut &[u8]) -> Result<u32, Error> {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(take(bytes, 4)?);
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn take_varint(bytes: &mut &[u8]) -> Result<u64, Error> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(bytes, 1)?[0];
+        n |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(invalid("varint overflow"))
+}
+
+/// Copies eight bytes for use with `u64::from_le_bytes`.
+fn le_bytes(bytes: &[u8]) -> [u8; 8] {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    buf
+}
+
+/// A CARv2 writer, that verifies and indexes each block.
+///
+/// As the header locates the data payload and index, it is written last,
+/// requiring the writer to be seekable.
+#[derive(Debug)]
+pub struct CarV2Writer<W> {
+    writer: W,
+    format: IndexFormat,
+    index: Index,
+    data_size: u64,
+}
+
+impl<W> CarV2Writer<W>
+where
+    W: AsyncWrite + AsyncSeek + Unpin + Send,
+{
+    /// Creates a new `CarV2Writer` with a `MultihashIndexSorted` index,
+    /// writing a CARv1 header with the given roots.
+    #[inline]
+    pub async fn new(roots: impl IntoIterator<Item = Cid>, writer: W) -> Result<Self, Error> {
+        Self::with_index_format(roots, writer, IndexFormat::MultihashIndexSorted).await
+    }
+
+    /// Creates a new `CarV2Writer` with an index of the given format.
+    pub async fn with_index_format(
+        roots: impl IntoIterator<Item = Cid>,
+        mut writer: W,
+        format: IndexFormat,
+    ) -> Result<Self, Error> {
+        writer.write_all(&PRAGMA).await.map_err(io_err)?;
+        writer.write_all(&[0u8; HEADER_LEN]).await.map_err(io_err)?;
+
+        let mut header_bytes = Vec::new();
+        DagCbor::write(&CarHeader::new(roots), &mut header_bytes)?;
+        let data_size = write_section(&mut writer, &[&header_bytes]).await?;
+
+        Ok(Self {
+            writer,
+            format,
+            index: Index::default(),
+            data_size,
+        })
+    }
+
+    /// Verifies, writes and indexes a block.
+    pub async fn write_block(&mut self, block: &Block) -> Result<(), Error> {
+        block.verify()?;
+        let cid_bytes = block.cid().to_bytes();
+        let len = write_section(&mut self.writer, &[&cid_bytes, block.bytes()]).await?;
+
+        self.index.insert(block.cid(), self.data_size);
+        self.data_size += len;
+        Ok(())
+    }
+
+    /// Verifies, writes and indexes a `Stream` of blocks.
+    pub async fn write_stream<St>(&mut self, blocks: St) -> Result<(), Error>
+    where
+        St: Stream<Item = Result<Block, Error>> + Send,
+    {
+        pin_mut!(blocks);
+        while let Some(block) = blocks.next().await {
+            self.write_block(&block?).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes the index and header, then flushes the writer, returning it.
+    pub async fn finish(mut self) -> Result<W, Error> {
+        let data_offset = (PRAGMA.len() + HEADER_LEN) as u64;
+        let header = CarV2Header {
+            characteristics: Characteristics::FULLY_INDEXED,
+            data_offset,
+            data_size: self.data_size,
+            index_offset: data_offset + self.data_size,
+        };
+
+        let index_bytes = self.index.to_bytes(self.format)?;
+        self.writer.write_all(&index_bytes).await.map_err(io_err)?;
+
+        let header_offset = SeekFrom::Start(PRAGMA.len() as u64);
+        self.writer.seek(header_offset).await.map_err(io_err)?;
+        self.writer
+            .write_all(&header.to_bytes())
+            .await
+            .map_err(io_err)?;
+        self.writer.flush().await.map_err(io_err)?;
+        Ok(self.writer)
+    }
+}
+
+/// A read-only `BlockStore` backed by an indexed CARv2 file, reading blocks
+/// from the file by their indexed offsets.
+#[derive(Clone, Debug)]
+pub struct CarBlockStore {
+    path: PathBuf,
+    header: CarV2Header,
+    roots: Vec<Cid>,
+    index: Index,
+}
+
+impl CarBlockStore {
+    /// Opens a CARv2 file, reading its headers and index.
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let mut file = open_file(&path).await?;
+
+        let mut pragma = [0u8; PRAGMA.len()];
+        file.read_exact(&mut pragma).await.map_err(io_err)?;
+        if pragma != PRAGMA {
+            return Err(invalid("missing CARv2 pragma"));
+        }
+
+        let mut header_bytes = [0u8; HEADER_LEN];
+        file.read_exact(&mut header_bytes).await.map_err(io_err)?;
+        let header = CarV2Header::from_bytes(&header_bytes)?;
+        if header.index_offset == 0 {
+            return Err(invalid("missing index"));
+        }
+
+        file.seek(SeekFrom::Start(header.data_offset))
+            .await
+            .map_err(io_err)?;
+        let roots = CarReader::new(&mut file)
+            .await?
+            .header()
+            .roots()
+            .cloned()
+            .collect();
+
+        let mut index_bytes = Vec::new();
+        file.seek(SeekFrom::Start(header.index_offset))
+            .await
+            .map_err(io_err)?;
+        file.read_to_end(&mut index_bytes).await.map_err(io_err)?;
+        let index = Index::from_bytes(&index_bytes)?;
+
+        Ok(Self {
+            path,
+            header,
+            roots,
+            index,
+        })
+    }
+
+    /// The path of the CAR file.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The CARv2 header.
+    #[inline]
+    pub fn header(&self) -> &CarV2Header {
+        &self.header
+    }
+
+    /// The CIDs of the CAR's root blocks.
+    #[inline]
+    pub fn roots(&self) -> &[Cid] {
+        &self.roots
+    }
+
+    /// The CAR's index.
+    #[inline]
+    pub fn index(&self) -> &Index {
+        &self.index
+    }
+
+    /// Reads the indexed block with the given CID, if any.
+    async fn find(&self, cid: &Cid) -> Result<Option<Block>, Error> {
+        let offsets: Vec<u64> = self.index.offsets(cid).collect();
+        if offsets.is_empty() {
+            return Ok(None);
+        }
+
+        let mut file = open_file(&self.path).await?;
+        for offset in offsets {
+            if offset >= self.header.data_size {
+                return Err(invalid(format!("index offset {} is out of range", offset)));
+            }
+
+            let pos = SeekFrom::Start(self.header.data_offset + offset);
+            file.seek(pos).await.map_err(io_err)?;
+            let section = read_section(&mut file)
+                .await?
+                .ok_or_else(|| invalid("unexpected end of data"))?;
+
+            let block = read_block(&section)?;
+            if block.cid() == cid {
+                return Ok(Some(block));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl BlockStore for CarBlockStore {
+    async fn get(&self, cid: &Cid) -> Result<Block, Error> {
+        self.find(cid)
+            .await?
+            .ok_or_else(|| Error::BlockNotFound(cid.to_string()))
+    }
+
+    async fn put(&self, _: Block) -> Result<(), Error> {
+        Err(read_only())
+    }
+
+    async fn has(&self, cid: &Cid) -> Result<bool, Error> {
+        Ok(self.find(cid).await?.is_some())
+    }
+
+    async fn delete(&self, _: &Cid) -> Result<(), Error> {
+        Err(read_only())
+    }
+}
+
+async fn open_file(path: &Path) -> Result<impl AsyncRead + AsyncSeek + Unpin + Send, Error> {
+    let path = path.to_path_buf();
+    let file = unblock(move || File::open(path)).await.map_err(io_err)?;
+    Ok(Unblock::new(file))
+}
+
+fn read_only() -> Error {
+    Error::BlockStore(anyhow::anyhow!("CAR block stores are read-only"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, io::Cursor, stream};
+
+    const SHA2_256: u64 = 0x12;
+
+    fn blocks() -> Vec<Block> {
+        vec![
+            Block::encode::<DagCbor, _>(&String::from("hello"), SHA2_256).unwrap(),
+            Block::encode::<DagCbor, _>(&vec![1u64, 2, 3], SHA2_256).unwrap(),
+            Block::encode::<DagCbor, _>(&true, 0x13).unwrap(),
+        ]
+    }
+
+    fn write_car(format: IndexFormat) -> Vec<u8> {
+        block_on(async {
+            let roots = vec![blocks()[0].cid().clone()];
+            let writer = Cursor::new(Vec::new());
+            let mut writer = CarV2Writer::with_index_format(roots, writer, format)
+                .await
+                .unwrap();
+            writer
+                .write_stream(stream::iter(blocks().into_iter().map(Ok)))
+                .await
+                .unwrap();
+            writer.finish().await.unwrap().into_inner()
+        })
+    }
+
+    #[test]
+    fn test_header() {
+        let header = CarV2Header {
+            characteristics: Characteristics::FULLY_INDEXED,
+            data_offset: 51,
+            data_size: 100,
+            index_offset: 151,
+        };
+        let bytes = header.to_bytes();
+        assert_eq!(bytes[0], 0x80);
+        assert_eq!(CarV2Header::from_bytes(&bytes).unwrap(), header);
+
+        let bytes = write_car(IndexFormat::MultihashIndexSorted);
+        assert_eq!(bytes[..PRAGMA.len()], PRAGMA);
+        let header = CarV2Header::from_bytes(&bytes[PRAGMA.len()..51]).unwrap();
+        assert!(header
+            .characteristics
+            .contains(Characteristics::FULLY_INDEXED));
+        assert_eq!(header.data_offset, 51);
+        assert_eq!(header.index_offset, 51 + header.data_size);
+
+        // the data payload is a valid CARv1
+        let data = &bytes[51..header.index_offset as usize];
+        block_on(async {
+            let mut reader = CarReader::new(data).await.unwrap();
+            for block in blocks() {
+                let (cid, _) = reader.next_block().await.unwrap().unwrap();
+                assert_eq!(&cid, block.cid());
+            }
+            assert!(reader.next_block().await.unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn test_index() {
+        let mut index = Index::default();
+        for (i, block) in blocks().iter().enumerate() {
+            index.insert(block.cid(), i as u64 * 100);
+        }
+
+        let bytes = index.to_bytes(IndexFormat::MultihashIndexSorted).unwrap();
+        assert_eq!(bytes[..2], [0x81, 0x08]);
+        assert_eq!(Index::from_bytes(&bytes).unwrap(), index);
+
+        let bytes = index.to_bytes(IndexFormat::IndexSorted).unwrap();
+        assert_eq!(bytes[..2], [0x80, 0x08]);
+        let sorted = Index::from_bytes(&bytes).unwrap();
+        for block in blocks() {
+            assert_eq!(
+                sorted.offsets(block.cid()).collect::<Vec<_>>(),
+                index.offsets(block.cid()).collect::<Vec<_>>(),
+            );
+        }
+        assert!(sorted.to_bytes(IndexFormat::MultihashIndexSorted).is_err());
+        assert!(Index::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_block_store() {
+        for format in &[IndexFormat::IndexSorted, IndexFormat::MultihashIndexSorted] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("test.car");
+            std::fs::write(&path, write_car(*format)).unwrap();
+
+            block_on(async {
+                let store = CarBlockStore::open(&path).await.unwrap();
+                assert_eq!(store.roots(), &[blocks()[0].cid().clone()]);
+
+                for block in blocks() {
+                    assert!(store.has(block.cid()).await.unwrap());
+                    assert_eq!(store.get(block.cid()).await.unwrap(), block);
+                }
+
+                let missing = Block::encode::<DagCbor, _>(&false, SHA2_256).unwrap();
+                assert!(!store.has(missing.cid()).await.unwrap());
+                match store.get(missing.cid()).await {
+                    Err(Error::BlockNotFound(_)) => {}
+                    res => panic!("expected BlockNotFound, got {:?}", res),
+                }
+
+                assert!(store.put(missing).await.is_err());
+                assert!(store.delete(blocks()[0].cid()).await.is_err());
+            });
+        }
+    }
+}
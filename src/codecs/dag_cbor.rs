@@ -1,23 +1,33 @@
 //! IPLD DagCbor codec.
 
 use crate::dev::*;
+use async_trait::async_trait;
+use delegate::delegate;
+use futures::io::{AsyncRead, AsyncReadExt};
 use serde::de;
 use serde_cbor::{
     de::Read as CborRead,
     from_reader, from_slice,
     ser::Write as CborWrite,
     tags::{current_cbor_tag, Tagged},
-    to_writer, Deserializer as CborDeserializer, Error as CborError, Serializer as CborSerializer,
+    Deserializer as CborDeserializer, Error as CborError, Serializer as CborSerializer,
+    Value as CborValue,
 };
 use std::{
     convert::TryFrom,
-    io::{Read, Write},
+    io::{self, Read, Write},
 };
 
 /// The magic tag signifying an IPLD link.
 pub const CBOR_LINK_TAG: u64 = 42;
 
+/// The multibase identity prefix preceding the bytes of a CID within a link.
+pub const CBOR_LINK_PREFIX: u8 = 0x00;
+
 /// The [DagCBOR](https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-cbor.md) codec, that delegates to `serde_cbor`.
+///
+/// Dags are always written in the canonical DagCBOR form, so that identical
+/// dags always produce identical bytes (and therefore identical CIDs).
 #[derive(Clone, Copy, Debug, Default)]
 pub struct DagCbor;
 
@@ -37,22 +47,30 @@ impl TryFrom<u64> for DagCbor {
     }
 }
 
+#[async_trait]
 impl Codec for DagCbor {
     const CODE: u64 = 0x71;
 
-    fn write<T, W>(dag: &T, writer: W) -> Result<(), Error>
+    /// Encodes the dag canonically (i.e. with sorted map keys, minimal-length
+    /// headers, no indefinite-length items and only 64-bit floats), rejecting
+    /// maps with duplicate keys.
+    fn write<T, W>(dag: &T, mut writer: W) -> Result<(), Error>
     where
         T: Representation + Serialize,
         W: Write,
     {
-        to_writer(writer, dag).map_err(|e| Error::Encoder(anyhow::Error::new(e)))
+        let mut bytes = Vec::new();
+        dag.serialize(canonical::CanonicalSerializer(&mut bytes))?;
+        writer
+            .write_all(&bytes)
+            .map_err(|e| Error::Encoder(anyhow::Error::new(e)))
     }
 
     fn decode<'de, T>(bytes: &'de [u8]) -> Result<T, Error>
     where
         T: Representation + Deserialize<'de>,
     {
-        from_slice(bytes).map_err(|e| Error::Decoder(anyhow::Error::new(e)))
+        from_slice(bytes).map_err(decode_error)
     }
 
     fn read<T, R>(reader: R) -> Result<T, Error>
@@ -60,69 +78,1326 @@ impl Codec for DagCbor {
         T: Representation + for<'de> Deserialize<'de>,
         R: Read,
     {
-        from_reader(reader).map_err(|e| Error::Decoder(anyhow::Error::new(e)))
+        from_reader(reader).map_err(decode_error)
+    }
+
+    /// Reads the bytes of a single CBOR item as they arrive, without reading
+    /// past its end (so that further items can be read from the same reader),
+    /// then decodes them.
+    async fn read_async<T, R>(mut reader: R) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+        R: AsyncRead + Unpin + Send,
+    {
+        let bytes = read_item(&mut reader)
+            .await
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        Self::decode(&bytes)
+    }
+}
+
+/// The maximum depth of nested items that will be read by `read_item`.
+const MAX_READ_DEPTH: usize = 256;
+
+/// Reads exactly the bytes of one (not necessarily canonical) CBOR item.
+async fn read_item<R>(mut reader: R) -> io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    fn invalid(msg: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    }
+
+    let mut bytes = Vec::new();
+    // the number of items left to read within each enclosing item, or `None`
+    // for indefinite-length items, which end with a break
+    let mut pending = vec![Some(1u64)];
+    while let Some(remaining) = pending.last_mut() {
+        if *remaining == Some(0) {
+            pending.pop();
+            continue;
+        }
+
+        let start = bytes.len();
+        read_exact(&mut reader, &mut bytes, 1).await?;
+        let initial = bytes[start];
+        if initial == 0xff {
+            match pending.pop() {
+                Some(None) => continue,
+                _ => return Err(invalid("unexpected break")),
+            }
+        }
+        if let Some(remaining) = remaining {
+            *remaining -= 1;
+        }
+
+        let arg_len = match initial & 0x1f {
+            0..=23 | 31 => 0,
+            info @ 24..=27 => 1 << (info - 24),
+            _ => return Err(invalid("reserved additional info")),
+        };
+        read_exact(&mut reader, &mut bytes, arg_len).await?;
+        let arg = match initial & 0x1f {
+            info @ 0..=23 => Some(u64::from(info)),
+            31 => None,
+            _ => Some(
+                bytes[start + 1..]
+                    .iter()
+                    .fold(0, |arg, byte| arg << 8 | u64::from(*byte)),
+            ),
+        };
+
+        match (initial >> 5, arg) {
+            (0, Some(_)) | (1, Some(_)) | (7, Some(_)) => {}
+            (2, Some(len)) | (3, Some(len)) => read_exact(&mut reader, &mut bytes, len).await?,
+            (2, None) | (3, None) | (4, None) | (5, None) => pending.push(None),
+            (4, Some(len)) => pending.push(Some(len)),
+            (5, Some(len)) => pending.push(Some(len.saturating_mul(2))),
+            (6, Some(_)) => pending.push(Some(1)),
+            _ => return Err(invalid("invalid indefinite-length item")),
+        }
+        if pending.len() > MAX_READ_DEPTH {
+            return Err(invalid("recursion limit exceeded"));
+        }
+    }
+    Ok(bytes)
+}
+
+/// Appends exactly `len` bytes from the reader to the buffer.
+async fn read_exact<R>(reader: &mut R, bytes: &mut Vec<u8>, len: u64) -> io::Result<()>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let read = reader.take(len).read_to_end(bytes).await?;
+    if read as u64 == len {
+        Ok(())
+    } else {
+        Err(io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+/// Maps a `serde_cbor` decoding error to an `Error`, recovering the typed
+/// error of any invalid link that caused it.
+///
+/// `serde_cbor` errors only retain the message of a custom error, so the typed
+/// error is parsed back from the message produced by `link_error`.
+fn decode_error(err: CborError) -> Error {
+    let msg = err.to_string();
+    if msg == Error::MissingLinkPrefix.to_string() {
+        return Error::MissingLinkPrefix;
+    }
+
+    let tag_msg = Error::UnexpectedCborTag(0).to_string();
+    match msg
+        .strip_prefix(tag_msg.trim_end_matches('0'))
+        .and_then(|tag| tag.parse().ok())
+    {
+        Some(tag) => Error::UnexpectedCborTag(tag),
+        None => Error::Decoder(anyhow::Error::new(err)),
+    }
+}
+
+/// Creates a `serde_cbor` error for an invalid link, from which `decode_error`
+/// recovers the typed error.
+fn link_error<E: de::Error>(err: Error) -> E {
+    E::custom(err)
+}
+
+impl DagCbor {
+    /// Given some bytes, deserialize a dag, rejecting any input that is not
+    /// canonical DagCBOR.
+    pub fn decode_strict<'de, T>(bytes: &'de [u8]) -> Result<T, Error>
+    where
+        T: Representation + Deserialize<'de>,
+    {
+        canonical::validate(bytes)?;
+        Self::decode(bytes)
+    }
+
+    /// Given a `Read`, deserialize a dag, rejecting any input that is not
+    /// canonical DagCBOR.
+    pub fn read_strict<T, R>(mut reader: R) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+        R: Read,
+    {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        Self::decode_strict(&bytes)
     }
 }
 
 impl<'a, W: CborWrite> Encoder for &'a mut CborSerializer<W> {
+    /// Serializes links as a tag-42 byte string, containing the bytes of the
+    /// `Cid` prefixed with the multibase identity prefix `0x00`.
     #[inline]
     fn serialize_link<S>(self, cid: &CidGeneric<S>) -> Result<Self::Ok, CborError>
     where
         S: MultihashSize,
     {
-        let bytes = cid.to_bytes();
-        Tagged::new(Some(CBOR_LINK_TAG), bytes.as_slice()).serialize(self)
+        let mut bytes = vec![CBOR_LINK_PREFIX];
+        bytes.extend_from_slice(&cid.to_bytes());
+        Tagged::new(Some(CBOR_LINK_TAG), CborValue::Bytes(bytes)).serialize(self)
     }
 }
 
 impl<'de, 'a, R: CborRead<'de>> Decoder<'de> for &'a mut CborDeserializer<R> {
+    /// Deserializes any IPLD data type, visiting tag-42 byte strings as links.
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, CborError>
+    where
+        V: IpldVisitorExt<'de>,
+    {
+        Deserializer::deserialize_any(self, CborVisitor(visitor))
+    }
+
+    /// Deserializes a tag-42 byte string as a link, stripping the multibase
+    /// identity prefix before visiting the bytes of the `Cid`.
     #[inline]
     fn deserialize_link<V>(self, visitor: V) -> Result<V::Value, CborError>
     where
         V: IpldVisitorExt<'de>,
+    {
+        Deserializer::deserialize_any(self, CborVisitor(visitor))
+    }
+}
+
+/// `CborVisitor` wraps an `IpldVisitorExt` in order to visit tag-42 byte
+/// strings as links.
+struct CborVisitor<V>(V);
+
+impl<'de, V: IpldVisitorExt<'de>> Visitor<'de> for CborVisitor<V> {
+    type Value = V::Value;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.expecting(formatter)
+    }
+
+    delegate! {
+        to self.0 {
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E>;
+            fn visit_i8<E: de::Error>(self, v: i8) -> Result<Self::Value, E>;
+            fn visit_i16<E: de::Error>(self, v: i16) -> Result<Self::Value, E>;
+            fn visit_i32<E: de::Error>(self, v: i32) -> Result<Self::Value, E>;
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E>;
+            fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E>;
+            fn visit_u8<E: de::Error>(self, v: u8) -> Result<Self::Value, E>;
+            fn visit_u16<E: de::Error>(self, v: u16) -> Result<Self::Value, E>;
+            fn visit_u32<E: de::Error>(self, v: u32) -> Result<Self::Value, E>;
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E>;
+            fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E>;
+            fn visit_f32<E: de::Error>(self, v: f32) -> Result<Self::Value, E>;
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E>;
+            fn visit_char<E: de::Error>(self, v: char) -> Result<Self::Value, E>;
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E>;
+            fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E>;
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E>;
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E>;
+            fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<Self::Value, E>;
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E>;
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E>;
+            fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error>;
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E>;
+            fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error>;
+            fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error>;
+            fn visit_enum<A: de::EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error>;
+        }
+    }
+
+    /// Called when a tagged value is found in the input data, which is the only
+    /// place `serde_cbor` exposes the current CBOR tag.
+    #[inline]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
     {
         match current_cbor_tag() {
             Some(CBOR_LINK_TAG) => {
-                // TODO:
-                // let bytes = <Box[u8]>::deserialize(self)?;
-                // let cid: Cid = bytes
-                //     .try_from()
-                //     .map_err(|e| de::Error::custom("expected a CID"))?;
-                // visitor.visit_link(cid)
-                unimplemented!()
+                let bytes = Deserializer::deserialize_bytes(deserializer, CidBytesVisitor)?;
+                match bytes.split_first() {
+                    Some((&CBOR_LINK_PREFIX, cid_bytes)) => self.0.visit_link(cid_bytes.into()),
+                    _ => Err(link_error(Error::MissingLinkPrefix)),
+                }
+            }
+            Some(tag) => Err(link_error(Error::UnexpectedCborTag(tag))),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::NewtypeStruct,
+                &self,
+            )),
+        }
+    }
+}
+
+/// Visits the (borrowed or owned) byte string contained within a tag-42 link.
+struct CidBytesVisitor;
+
+impl<'de> Visitor<'de> for CidBytesVisitor {
+    type Value = Box<[u8]>;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a byte string containing a multibase-prefixed CID")
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, bytes: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(bytes.into())
+    }
+
+    #[inline]
+    fn visit_byte_buf<E>(self, bytes: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(bytes.into_boxed_slice())
+    }
+}
+
+/// Canonical DagCBOR encoding and validation.
+mod canonical {
+    use super::{CBOR_LINK_PREFIX, CBOR_LINK_TAG};
+    use crate::dev::*;
+    use serde::ser;
+    use std::{cmp::Ordering, convert::TryFrom, str};
+
+    /// The maximum depth of nested lists and maps that will be validated.
+    const MAX_DEPTH: usize = 256;
+
+    fn non_canonical(msg: impl Into<String>) -> Error {
+        Error::NonCanonical(msg.into())
+    }
+
+    /// Orders encoded map keys by length first, then bytewise.
+    fn cmp_keys(a: &[u8], b: &[u8]) -> Ordering {
+        a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+    }
+
+    /// Writes a major type and its argument using the fewest bytes possible.
+    fn write_header(buf: &mut Vec<u8>, major: u8, arg: u64) {
+        let major = major << 5;
+        match arg {
+            0..=23 => buf.push(major | arg as u8),
+            24..=0xff => buf.extend_from_slice(&[major | 24, arg as u8]),
+            0x100..=0xffff => {
+                buf.push(major | 25);
+                buf.extend_from_slice(&(arg as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                buf.push(major | 26);
+                buf.extend_from_slice(&(arg as u32).to_be_bytes());
+            }
+            _ => {
+                buf.push(major | 27);
+                buf.extend_from_slice(&arg.to_be_bytes());
+            }
+        }
+    }
+
+    fn write_str(buf: &mut Vec<u8>, s: &str) {
+        write_header(buf, 3, s.len() as u64);
+        buf.extend_from_slice(s.as_bytes());
+    }
+
+    /// Writes the header and key of the single-entry map that represents an
+    /// enum variant.
+    fn write_variant(buf: &mut Vec<u8>, variant: &str) {
+        write_header(buf, 5, 1);
+        write_str(buf, variant);
+    }
+
+    /// The string of an encoded map key, which must be a string.
+    fn key_str(key: &[u8]) -> Result<&str, Error> {
+        let mut reader = Validator(key);
+        match reader.header()? {
+            (3, _, len) => reader.take_str(len),
+            _ => Err(non_canonical("map keys must be strings")),
+        }
+    }
+
+    /// A `Serializer` that writes canonical DagCBOR to a buffer in a single
+    /// pass, sorting map entries as they are written and rejecting maps with
+    /// duplicate keys.
+    pub(super) struct CanonicalSerializer<'a>(pub(super) &'a mut Vec<u8>);
+
+    impl<'a> Encoder for CanonicalSerializer<'a> {
+        /// Serializes links as a tag-42 byte string, containing the bytes of
+        /// the `Cid` prefixed with the multibase identity prefix `0x00`.
+        #[inline]
+        fn serialize_link<S>(self, cid: &CidGeneric<S>) -> Result<Self::Ok, Self::Error>
+        where
+            S: MultihashSize,
+        {
+            let cid_bytes = cid.to_bytes();
+            write_header(self.0, 6, CBOR_LINK_TAG);
+            write_header(self.0, 2, cid_bytes.len() as u64 + 1);
+            self.0.push(CBOR_LINK_PREFIX);
+            self.0.extend_from_slice(&cid_bytes);
+            Ok(())
+        }
+    }
+
+    macro_rules! serialize_int {
+        ($($type:ty : $serialize_fn:ident => $delegate_fn:ident,)*) => {
+            $(
+                #[inline]
+                fn $serialize_fn(self, n: $type) -> Result<Self::Ok, Self::Error> {
+                    self.$delegate_fn(n.into())
+                }
+            )*
+        };
+    }
+
+    impl<'a> Serializer for CanonicalSerializer<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        type SerializeSeq = SerializeList<'a>;
+        type SerializeTuple = SerializeList<'a>;
+        type SerializeTupleStruct = SerializeList<'a>;
+        type SerializeTupleVariant = SerializeList<'a>;
+        type SerializeMap = SerializeMap<'a>;
+        type SerializeStruct = SerializeMap<'a>;
+        type SerializeStructVariant = SerializeMap<'a>;
+
+        #[inline]
+        fn serialize_bool(self, b: bool) -> Result<Self::Ok, Self::Error> {
+            self.0.push(if b { 0xf5 } else { 0xf4 });
+            Ok(())
+        }
+
+        serialize_int! {
+            i8: serialize_i8 => serialize_i64,
+            i16: serialize_i16 => serialize_i64,
+            i32: serialize_i32 => serialize_i64,
+            u8: serialize_u8 => serialize_u64,
+            u16: serialize_u16 => serialize_u64,
+            u32: serialize_u32 => serialize_u64,
+        }
+
+        #[inline]
+        fn serialize_i64(self, n: i64) -> Result<Self::Ok, Self::Error> {
+            if n >= 0 {
+                write_header(self.0, 0, n as u64);
+            } else {
+                write_header(self.0, 1, (-1 - n) as u64);
+            }
+            Ok(())
+        }
+
+        #[inline]
+        fn serialize_i128(self, n: i128) -> Result<Self::Ok, Self::Error> {
+            let (major, arg) = if n >= 0 { (0, n) } else { (1, -1 - n) };
+            let arg = u64::try_from(arg)
+                .map_err(|_| non_canonical(format!("integer {} out of range", n)))?;
+            write_header(self.0, major, arg);
+            Ok(())
+        }
+
+        #[inline]
+        fn serialize_u64(self, n: u64) -> Result<Self::Ok, Self::Error> {
+            write_header(self.0, 0, n);
+            Ok(())
+        }
+
+        #[inline]
+        fn serialize_u128(self, n: u128) -> Result<Self::Ok, Self::Error> {
+            let n = u64::try_from(n)
+                .map_err(|_| non_canonical(format!("integer {} out of range", n)))?;
+            self.serialize_u64(n)
+        }
+
+        #[inline]
+        fn serialize_f32(self, n: f32) -> Result<Self::Ok, Self::Error> {
+            self.serialize_f64(n.into())
+        }
+
+        #[inline]
+        fn serialize_f64(self, n: f64) -> Result<Self::Ok, Self::Error> {
+            if !n.is_finite() {
+                return Err(non_canonical(format!("unsupported float {}", n)));
+            }
+            self.0.push(0xfb);
+            self.0.extend_from_slice(&n.to_bits().to_be_bytes());
+            Ok(())
+        }
+
+        #[inline]
+        fn serialize_char(self, c: char) -> Result<Self::Ok, Self::Error> {
+            self.serialize_str(c.encode_utf8(&mut [0; 4]))
+        }
+
+        #[inline]
+        fn serialize_str(self, s: &str) -> Result<Self::Ok, Self::Error> {
+            write_str(self.0, s);
+            Ok(())
+        }
+
+        #[inline]
+        fn serialize_bytes(self, bytes: &[u8]) -> Result<Self::Ok, Self::Error> {
+            write_header(self.0, 2, bytes.len() as u64);
+            self.0.extend_from_slice(bytes);
+            Ok(())
+        }
+
+        #[inline]
+        fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+            self.0.push(0xf6);
+            Ok(())
+        }
+
+        #[inline]
+        fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            value.serialize(self)
+        }
+
+        #[inline]
+        fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+            self.serialize_none()
+        }
+
+        #[inline]
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+            self.serialize_none()
+        }
+
+        #[inline]
+        fn serialize_unit_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+        ) -> Result<Self::Ok, Self::Error> {
+            self.serialize_str(variant)
+        }
+
+        #[inline]
+        fn serialize_newtype_struct<T>(
+            self,
+            _name: &'static str,
+            value: &T,
+        ) -> Result<Self::Ok, Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            value.serialize(self)
+        }
+
+        #[inline]
+        fn serialize_newtype_variant<T>(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            value: &T,
+        ) -> Result<Self::Ok, Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            write_variant(self.0, variant);
+            value.serialize(self)
+        }
+
+        #[inline]
+        fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+            Ok(SerializeList::new(self.0, len))
+        }
+
+        #[inline]
+        fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+            self.serialize_seq(Some(len))
+        }
+
+        #[inline]
+        fn serialize_tuple_struct(
+            self,
+            _name: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+            self.serialize_seq(Some(len))
+        }
+
+        #[inline]
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+            write_variant(self.0, variant);
+            self.serialize_seq(Some(len))
+        }
+
+        #[inline]
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+            Ok(SerializeMap::new(self.0))
+        }
+
+        #[inline]
+        fn serialize_struct(
+            self,
+            _name: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeStruct, Self::Error> {
+            self.serialize_map(Some(len))
+        }
+
+        #[inline]
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            len: usize,
+        ) -> Result<Self::SerializeStructVariant, Self::Error> {
+            write_variant(self.0, variant);
+            self.serialize_map(Some(len))
+        }
+    }
+
+    /// Serializes a list, writing its elements directly to the buffer if its
+    /// length is known upfront, and otherwise buffering them until it is.
+    pub(super) struct SerializeList<'a> {
+        buf: &'a mut Vec<u8>,
+        len: Option<usize>,
+        count: usize,
+        elems: Vec<u8>,
+    }
+
+    impl<'a> SerializeList<'a> {
+        fn new(buf: &'a mut Vec<u8>, len: Option<usize>) -> Self {
+            if let Some(len) = len {
+                write_header(buf, 4, len as u64);
+            }
+            Self {
+                buf,
+                len,
+                count: 0,
+                elems: Vec::new(),
+            }
+        }
+
+        fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            self.count += 1;
+            match self.len {
+                Some(_) => value.serialize(CanonicalSerializer(&mut *self.buf)),
+                None => value.serialize(CanonicalSerializer(&mut self.elems)),
+            }
+        }
+
+        fn finish(self) -> Result<(), Error> {
+            match self.len {
+                Some(len) if len != self.count => Err(<Error as ser::Error>::custom(format!(
+                    "expected {} list elements, found {}",
+                    len, self.count
+                ))),
+                Some(_) => Ok(()),
+                None => {
+                    write_header(self.buf, 4, self.count as u64);
+                    self.buf.extend_from_slice(&self.elems);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl<'a> ser::SerializeSeq for SerializeList<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        #[inline]
+        fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.push(value)
+        }
+
+        #[inline]
+        fn end(self) -> Result<Self::Ok, Self::Error> {
+            self.finish()
+        }
+    }
+
+    impl<'a> ser::SerializeTuple for SerializeList<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        #[inline]
+        fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.push(value)
+        }
+
+        #[inline]
+        fn end(self) -> Result<Self::Ok, Self::Error> {
+            self.finish()
+        }
+    }
+
+    impl<'a> ser::SerializeTupleStruct for SerializeList<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        #[inline]
+        fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.push(value)
+        }
+
+        #[inline]
+        fn end(self) -> Result<Self::Ok, Self::Error> {
+            self.finish()
+        }
+    }
+
+    impl<'a> ser::SerializeTupleVariant for SerializeList<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        #[inline]
+        fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.push(value)
+        }
+
+        #[inline]
+        fn end(self) -> Result<Self::Ok, Self::Error> {
+            self.finish()
+        }
+    }
+
+    /// Serializes a map, buffering its encoded entries so that they can be
+    /// sorted by their encoded keys.
+    pub(super) struct SerializeMap<'a> {
+        buf: &'a mut Vec<u8>,
+        entries: Vec<(Vec<u8>, Vec<u8>)>,
+        key: Option<Vec<u8>>,
+    }
+
+    impl<'a> SerializeMap<'a> {
+        fn new(buf: &'a mut Vec<u8>) -> Self {
+            Self {
+                buf,
+                entries: Vec::new(),
+                key: None,
+            }
+        }
+
+        fn push_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+            let mut key_bytes = Vec::new();
+            key.serialize(CanonicalSerializer(&mut key_bytes))?;
+            key_str(&key_bytes)?;
+            self.key = Some(key_bytes);
+            Ok(())
+        }
+
+        fn push_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+            let key = self.key.take().ok_or_else(|| {
+                <Error as ser::Error>::custom("map value serialized before its key")
+            })?;
+            let mut value_bytes = Vec::new();
+            value.serialize(CanonicalSerializer(&mut value_bytes))?;
+            self.entries.push((key, value_bytes));
+            Ok(())
+        }
+
+        fn finish(mut self) -> Result<(), Error> {
+            self.entries.sort_by(|(a, _), (b, _)| cmp_keys(a, b));
+            if let Some(pair) = self.entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                let key = key_str(&pair[0].0)?;
+                return Err(non_canonical(format!("duplicate map key `{}`", key)));
+            }
+
+            write_header(self.buf, 5, self.entries.len() as u64);
+            for (key, value) in self.entries {
+                self.buf.extend_from_slice(&key);
+                self.buf.extend_from_slice(&value);
+            }
+            Ok(())
+        }
+    }
+
+    impl<'a> ser::SerializeMap for SerializeMap<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        #[inline]
+        fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.push_key(key)
+        }
+
+        #[inline]
+        fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.push_value(value)
+        }
+
+        #[inline]
+        fn end(self) -> Result<Self::Ok, Self::Error> {
+            self.finish()
+        }
+    }
+
+    impl<'a> ser::SerializeStruct for SerializeMap<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        #[inline]
+        fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.push_key(key)?;
+            self.push_value(value)
+        }
+
+        #[inline]
+        fn end(self) -> Result<Self::Ok, Self::Error> {
+            self.finish()
+        }
+    }
+
+    impl<'a> ser::SerializeStructVariant for SerializeMap<'a> {
+        type Ok = ();
+        type Error = Error;
+
+        #[inline]
+        fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+        where
+            T: Serialize + ?Sized,
+        {
+            self.push_key(key)?;
+            self.push_value(value)
+        }
+
+        #[inline]
+        fn end(self) -> Result<Self::Ok, Self::Error> {
+            self.finish()
+        }
+    }
+
+    /// Validates that the bytes contain exactly one canonical DagCBOR item.
+    pub(super) fn validate(bytes: &[u8]) -> Result<(), Error> {
+        let mut validator = Validator(bytes);
+        validator.validate_item(0)?;
+        if validator.0.is_empty() {
+            Ok(())
+        } else {
+            Err(non_canonical("trailing bytes after data item"))
+        }
+    }
+
+    struct Validator<'a>(&'a [u8]);
+
+    impl<'a> Validator<'a> {
+        fn take(&mut self, len: u64) -> Result<&'a [u8], Error> {
+            match usize::try_from(len) {
+                Ok(len) if len <= self.0.len() => {
+                    let (head, tail) = self.0.split_at(len);
+                    self.0 = tail;
+                    Ok(head)
+                }
+                _ => Err(non_canonical("unexpected end of input")),
+            }
+        }
+
+        /// Reads an item's major type, additional info and argument,
+        /// asserting that any integer or length argument is minimally encoded.
+        fn header(&mut self) -> Result<(u8, u8, u64), Error> {
+            let (major, info, arg, is_minimal) = self.any_header()?;
+            let arg =
+                arg.ok_or_else(|| non_canonical("indefinite-length items are not allowed"))?;
+
+            // major type 7 arguments are floats and simple values, not lengths
+            if major != 7 && !is_minimal {
+                return Err(non_canonical(
+                    "integers and lengths must be minimally encoded",
+                ));
+            }
+            Ok((major, info, arg))
+        }
+
+        /// Reads an item's major type, additional info and argument (`None`
+        /// if indefinite-length), and whether the argument is minimally
+        /// encoded.
+        fn any_header(&mut self) -> Result<(u8, u8, Option<u64>, bool), Error> {
+            let initial = self.take(1)?[0];
+            let (major, info) = (initial >> 5, initial & 0x1f);
+            let (arg, is_minimal) = match info {
+                0..=23 => (u64::from(info), true),
+                24 => {
+                    let arg = u64::from(self.take(1)?[0]);
+                    (arg, arg > 23)
+                }
+                25 => {
+                    let mut arg = [0; 2];
+                    arg.copy_from_slice(self.take(2)?);
+                    let arg = u64::from(u16::from_be_bytes(arg));
+                    (arg, arg > 0xff)
+                }
+                26 => {
+                    let mut arg = [0; 4];
+                    arg.copy_from_slice(self.take(4)?);
+                    let arg = u64::from(u32::from_be_bytes(arg));
+                    (arg, arg > 0xffff)
+                }
+                27 => {
+                    let mut arg = [0; 8];
+                    arg.copy_from_slice(self.take(8)?);
+                    let arg = u64::from_be_bytes(arg);
+                    (arg, arg > 0xffff_ffff)
+                }
+                31 => return Ok((major, info, None, true)),
+                _ => return Err(non_canonical("reserved additional info")),
+            };
+            Ok((major, info, Some(arg), is_minimal))
+        }
+
+        fn validate_item(&mut self, depth: usize) -> Result<(), Error> {
+            if depth > MAX_DEPTH {
+                return Err(non_canonical("recursion limit exceeded"));
+            }
+
+            match self.header()? {
+                (0, _, _) | (1, _, _) => Ok(()),
+                (2, _, len) => self.take(len).map(|_| ()),
+                (3, _, len) => self.take_str(len).map(|_| ()),
+                (4, _, len) => (0..len).try_for_each(|_| self.validate_item(depth + 1)),
+                (5, _, len) => self.validate_map(len, depth),
+                (6, _, CBOR_LINK_TAG) => self.validate_link(),
+                (6, _, tag) => Err(Error::UnexpectedCborTag(tag)),
+                (7, 20..=22, _) => Ok(()),
+                (7, 23, _) => Err(non_canonical("`undefined` is not allowed")),
+                (7, 27, bits) if f64::from_bits(bits).is_finite() => Ok(()),
+                (7, 27, _) => Err(non_canonical("NaN and infinite floats are not allowed")),
+                (7, 25, _) | (7, 26, _) => Err(non_canonical("floats must be 64-bit")),
+                _ => Err(non_canonical("unsupported simple value")),
+            }
+        }
+
+        fn take_str(&mut self, len: u64) -> Result<&'a str, Error> {
+            str::from_utf8(self.take(len)?).map_err(|_| non_canonical("invalid UTF-8 string"))
+        }
+
+        fn validate_map(&mut self, len: u64, depth: usize) -> Result<(), Error> {
+            let mut prev_key: Option<&'a str> = None;
+            for _ in 0..len {
+                let key = match self.header()? {
+                    (3, _, len) => self.take_str(len)?,
+                    _ => return Err(non_canonical("map keys must be strings")),
+                };
+
+                if let Some(prev_key) = prev_key {
+                    match cmp_keys(prev_key.as_bytes(), key.as_bytes()) {
+                        Ordering::Less => {}
+                        Ordering::Equal => {
+                            return Err(non_canonical(format!("duplicate map key `{}`", key)))
+                        }
+                        Ordering::Greater => {
+                            return Err(non_canonical(format!(
+                                "map key `{}` must be sorted before `{}`",
+                                key, prev_key
+                            )))
+                        }
+                    }
+                }
+
+                prev_key = Some(key);
+                self.validate_item(depth + 1)?;
+            }
+            Ok(())
+        }
+
+        fn validate_link(&mut self) -> Result<(), Error> {
+            match self.header()? {
+                (2, _, len) => match self.take(len)?.first() {
+                    Some(&CBOR_LINK_PREFIX) => Ok(()),
+                    _ => Err(Error::MissingLinkPrefix),
+                },
+                _ => Err(non_canonical("links must be tagged byte strings")),
             }
-            Some(tag) => Err(de::Error::custom(format!(
-                "unexpected CBOR tag for CID: {}",
-                tag
-            ))),
-            _ => Err(de::Error::custom("expected a CID")),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        _codecs::{test_schemas::*, test_utils::*},
+        prelude::*,
+    };
+    use std::str::FromStr;
+
+    fn roundtrip<'de, T>(cases: &[(T, &'de [u8])])
+    where
+        T: PartialEq + Debug + Representation + Serialize + DeserializeOwned,
+    {
+        roundtrip_bytes_codec::<DagCbor, T>(cases)
+    }
+
+    fn link_bytes(tag: u8, prefix: Option<u8>, cid: &CidGeneric<typenum::U32>) -> Vec<u8> {
+        let cid_bytes = cid.to_bytes();
+        let len = cid_bytes.len() + prefix.iter().count();
+        let mut bytes = vec![0xd8, tag, 0x58, len as u8];
+        bytes.extend(prefix);
+        bytes.extend_from_slice(&cid_bytes);
+        bytes
+    }
+
+    #[test]
+    fn test_null() {
+        let tests = &[((), &[0xf6][..])];
+        roundtrip(tests);
+        let tests = &[(None as Option<Int>, &[0xf6][..])];
+        roundtrip(tests);
+    }
+
+    #[test]
+    fn test_bool() {
+        let tests = &[(true, &[0xf5][..]), (false, &[0xf4][..])];
+        roundtrip(tests);
+    }
+
+    #[test]
+    fn test_number() {
+        let tests = &[
+            (23, &[0x17][..]),
+            (123, &[0x18, 0x7b][..]),
+            (1000, &[0x19, 0x03, 0xe8][..]),
+            (-1000, &[0x39, 0x03, 0xe7][..]),
+        ];
+        roundtrip(tests);
+
+        // floats are always encoded as 64-bit
+        let tests = &[
+            (
+                1.5,
+                &[0xfb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00][..],
+            ),
+            (
+                -4.1,
+                &[0xfb, 0xc0, 0x10, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66][..],
+            ),
+        ];
+        roundtrip(tests);
+    }
+
+    #[test]
+    fn test_string() {
+        let tests = &[(String::from("a"), &[0x61, 0x61][..])];
+        roundtrip(tests);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let tests = &[
+            (Bytes::from(vec![]), &[0x40][..]),
+            (
+                Bytes::from(vec![0x01, 0x02, 0x03]),
+                &[0x43, 0x01, 0x02, 0x03][..],
+            ),
+        ];
+        roundtrip(tests);
+
+        let bytes = Bytes::from(vec![0xff; 24]);
+        let mut expected = vec![0x58, 24];
+        expected.extend_from_slice(&bytes);
+        roundtrip(&[(bytes, &expected[..])]);
+    }
+
+    #[test]
+    fn test_link() {
+        let s = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+        let cid = CidGeneric::<typenum::U32>::from_str(s).unwrap();
+        let bytes = link_bytes(0x2a, Some(0x00), &cid);
+
+        let tests = &[(Link::<Null, typenum::U32>::from(cid), bytes.as_slice())];
+        roundtrip(tests);
+
+        let s = "bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily";
+        let cid = CidGeneric::<typenum::U32>::from_str(s).unwrap();
+        let bytes = link_bytes(0x2a, Some(0x00), &cid);
+
+        let tests = &[(Link::<Null, typenum::U32>::from(cid), bytes.as_slice())];
+        roundtrip(tests);
+    }
+
     #[test]
-    fn test_null() {}
+    fn test_invalid_link() {
+        let s = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+        let cid = CidGeneric::<typenum::U32>::from_str(s).unwrap();
+
+        // missing multibase prefix
+        let bytes = link_bytes(0x2a, None, &cid);
+        let err = DagCbor::decode::<Link<Null, typenum::U32>>(&bytes).unwrap_err();
+        assert!(matches!(err, Error::MissingLinkPrefix), "{}", err);
+        let err = DagCbor::read::<Link<Null, typenum::U32>, _>(bytes.as_slice()).unwrap_err();
+        assert!(matches!(err, Error::MissingLinkPrefix), "{}", err);
+
+        // unexpected tag
+        let bytes = link_bytes(0x2b, Some(0x00), &cid);
+        let err = DagCbor::decode::<Link<Null, typenum::U32>>(&bytes).unwrap_err();
+        assert!(matches!(err, Error::UnexpectedCborTag(0x2b)), "{}", err);
+
+        // untagged bytes, not reported as the previous link error
+        let bytes = &link_bytes(0x2a, Some(0x00), &cid)[2..];
+        let err = DagCbor::decode::<Link<Null, typenum::U32>>(bytes).unwrap_err();
+        assert!(matches!(err, Error::Decoder(_)), "{}", err);
+
+        // malformed CID
+        let bytes = [0xd8, 0x2a, 0x43, 0x00, 0x01, 0x71];
+        assert!(DagCbor::decode::<Link<Null, typenum::U32>>(&bytes).is_err());
+    }
 
     #[test]
-    fn test_bool() {}
+    fn test_seq() {
+        let tests = &[(
+            Names(vec!["a".into(), "b".into()]),
+            &[0x82, 0x61, 0x61, 0x61, 0x62][..],
+        )];
+        roundtrip(tests);
+
+        let tests = &[(
+            NullableInts(vec![Some(Int::from(1)), None]),
+            &[0x82, 0x01, 0xf6][..],
+        )];
+        roundtrip(tests);
+        assert!(DagCbor::decode::<Names>(&[0x82, 0x61, 0x61, 0xf6]).is_err());
+    }
 
     #[test]
-    fn test_number() {}
+    fn test_map() {
+        // keys are sorted by length first, then bytewise
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(String::from("bb"), Int::from(1));
+        map.insert(String::from("c"), Int::from(2));
+        map.insert(String::from("a"), Int::from(3));
+        let bytes = [
+            0xa3, 0x61, 0x61, 0x03, 0x61, 0x63, 0x02, 0x62, 0x62, 0x62, 0x01,
+        ];
+
+        let tests = &[(map, &bytes[..])];
+        roundtrip(tests);
+
+        let map = || {
+            let mut map = std::collections::BTreeMap::new();
+            map.insert(String::from("a"), Int::from(1));
+            map.insert(String::from("bb"), Int::from(2));
+            map
+        };
+
+        let bytes = [0xa2, 0x61, 0x61, 0x01, 0x62, 0x62, 0x62, 0x02];
+        let tests = &[(Counts(map()), &bytes[..])];
+        roundtrip(tests);
+
+        let bytes = [0x82, 0x82, 0x61, 0x61, 0x01, 0x82, 0x62, 0x62, 0x62, 0x02];
+        let tests = &[(PairCounts(map()), &bytes[..])];
+        roundtrip(tests);
+
+        let bytes = [0x68, b'a', b'=', b'1', b'&', b'b', b'b', b'=', b'2'];
+        let tests = &[(QueryCounts(map()), &bytes[..])];
+        roundtrip(tests);
+    }
 
     #[test]
-    fn test_string() {}
+    fn test_duplicate_keys() {
+        struct Duplicates;
+
+        impl Representation for Duplicates {
+            const NAME: &'static str = "Duplicates";
+        }
+
+        impl Serialize for Duplicates {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("a", &1)?;
+                map.serialize_entry("b", &2)?;
+                map.serialize_entry("a", &3)?;
+                map.end()
+            }
+        }
+
+        let err = DagCbor::write(&Duplicates, Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::NonCanonical(_)), "{}", err);
+    }
 
     #[test]
-    fn test_bytes() {}
+    fn test_struct() {
+        let point = |label: Option<&str>| Point {
+            x: Int::from(1),
+            y: Int::from(2),
+            label: label.map(String::from),
+        };
+        let tests = &[
+            (point(Some("a")), &[0x83, 0x02, 0x01, 0x61, b'a'][..]),
+            (point(None), &[0x82, 0x02, 0x01][..]),
+        ];
+        roundtrip(tests);
+
+        // an absent optional nullable field is distinct from a null one
+        let range = |end: Option<Option<i32>>, step: Option<i32>| Range {
+            start: Int::from(1),
+            end: end.map(|end| end.map(Int::from)),
+            step: step.map(Int::from),
+        };
+        let tests = &[
+            (range(None, None), &[0x81, 0x01][..]),
+            (range(Some(Some(5)), None), &[0x82, 0x01, 0x05][..]),
+            (range(Some(None), None), &[0x82, 0x01, 0xf6][..]),
+            (range(Some(None), Some(2)), &[0x83, 0x01, 0xf6, 0x02][..]),
+        ];
+        roundtrip(tests);
+        assert!(DagCbor::write(&range(None, Some(2)), Vec::new()).is_err());
+    }
 
     #[test]
-    fn test_link() {}
+    fn test_union() {
+        let circle = || Circle {
+            radius: Int::from(1),
+        };
+
+        // map keys are written in canonical order
+        let bytes = [
+            &[0xa2, 0x63][..],
+            b"tag",
+            &[0x66],
+            b"circle",
+            &[0x67],
+            b"content",
+            &[0xa1, 0x66],
+            b"radius",
+            &[0x01],
+        ]
+        .concat();
+        let tests = &[(Shape::Circle(circle()), &bytes[..])];
+        roundtrip(tests);
+
+        let bytes = [
+            &[0xa2, 0x63][..],
+            b"tag",
+            &[0x66],
+            b"circle",
+            &[0x66],
+            b"radius",
+            &[0x01],
+        ]
+        .concat();
+        let tests = &[(InlineShape::Circle(circle()), &bytes[..])];
+        roundtrip(tests);
+
+        // duplicate member keys are rejected
+        let bytes = [
+            &[0xa3, 0x63][..],
+            b"tag",
+            &[0x66],
+            b"circle",
+            &[0x66],
+            b"radius",
+            &[0x01, 0x66],
+            b"radius",
+            &[0x02],
+        ]
+        .concat();
+        assert!(DagCbor::decode::<InlineShape>(&bytes).is_err());
+    }
 
     #[test]
-    fn test_seq() {}
+    fn test_async() {
+        use futures::{executor::block_on, io::Cursor};
+
+        let mut bytes = Vec::new();
+        block_on(DagCbor::write_async(&true, &mut bytes)).unwrap();
+        assert_eq!(bytes, vec![0xf5]);
+
+        let v: bool = block_on(DagCbor::read_async(Cursor::new(bytes))).unwrap();
+        assert!(v);
+
+        // items are read one at a time from the same reader
+        let bytes = [
+            &[0x82, 0x01, 0x61, b'a'][..],
+            &[0xbf, 0x61, b'b', 0x9f, 0xf5, 0xff, 0xff],
+            &[0xd8, 0x2a, 0x41, 0x00],
+            &[0xf5],
+        ]
+        .concat();
+        let mut reader = Cursor::new(bytes);
+        let list: Value = block_on(DagCbor::read_async(&mut reader)).unwrap();
+        assert_eq!(list, Value::List(vec![1u8.into(), "a".into()]));
+        assert_eq!(reader.position(), 4);
+        let map: Value = block_on(DagCbor::read_async(&mut reader)).unwrap();
+        assert_eq!(map["b"][0].as_bool(), Some(true));
+        assert_eq!(reader.position(), 11);
+        assert!(block_on(DagCbor::read_async::<Value, _>(&mut reader)).is_err());
+        assert_eq!(reader.position(), 15);
+        let v: bool = block_on(DagCbor::read_async(&mut reader)).unwrap();
+        assert!(v);
+
+        // truncated items
+        let bytes = [0x82, 0x01];
+        assert!(block_on(DagCbor::read_async::<Value, _>(Cursor::new(bytes))).is_err());
+    }
 
     #[test]
-    fn test_map() {}
+    fn test_strict() {
+        type Map = std::collections::BTreeMap<String, Int>;
+
+        // canonical
+        let bytes = [0xa2, 0x61, 0x61, 0x01, 0x62, 0x62, 0x62, 0x02];
+        assert!(DagCbor::decode_strict::<Map>(&bytes).is_ok());
+
+        // unsorted keys
+        let bytes = [0xa2, 0x62, 0x62, 0x62, 0x02, 0x61, 0x61, 0x01];
+        assert!(DagCbor::decode::<Map>(&bytes).is_ok());
+        assert!(DagCbor::decode_strict::<Map>(&bytes).is_err());
+
+        // duplicate keys
+        let bytes = [0xa2, 0x61, 0x61, 0x01, 0x61, 0x61, 0x02];
+        assert!(DagCbor::decode_strict::<Map>(&bytes).is_err());
+
+        // indefinite-length map
+        let bytes = [0xbf, 0x61, 0x61, 0x01, 0xff];
+        assert!(DagCbor::decode::<Map>(&bytes).is_ok());
+        assert!(DagCbor::decode_strict::<Map>(&bytes).is_err());
+
+        // non-minimal integer
+        let bytes = [0x18, 0x01];
+        assert!(DagCbor::decode::<Int>(&bytes).is_ok());
+        assert!(DagCbor::decode_strict::<Int>(&bytes).is_err());
+
+        // 32-bit float
+        let bytes = [0xfa, 0x3f, 0xc0, 0x00, 0x00];
+        assert!(DagCbor::decode::<Float>(&bytes).is_ok());
+        assert!(DagCbor::decode_strict::<Float>(&bytes).is_err());
+
+        // undefined
+        let bytes = [0xf7];
+        assert!(DagCbor::decode::<()>(&bytes).is_ok());
+        assert!(DagCbor::decode_strict::<()>(&bytes).is_err());
+
+        // tags other than 42
+        let bytes = [0xc1, 0x01];
+        assert!(DagCbor::decode_strict::<Int>(&bytes).is_err());
+    }
 }
@@ -1,23 +1,29 @@
 //! IPLD DagJson codec.
 
 use crate::dev::*;
+use async_trait::async_trait;
 use delegate::delegate;
+use futures::io::{AsyncRead, AsyncReadExt};
 use serde::{de, ser};
-#[cfg(not(feature = "simd"))]
 use serde_json::{
     de::Read as JsonRead, from_reader, from_slice, to_writer, Deserializer as JsonDeserializer,
-    Error as JsonError, Serializer as JsonSerializer,
+    Error as JsonError, Map as JsonMap, Serializer as JsonSerializer, Value as JsonValue,
 };
-// #[cfg(feature = "simd")]
-// use simd_json::{};
-use std::convert::TryFrom;
+#[cfg(feature = "simd")]
+use simd_json::{Deserializer as SimdDeserializer, Error as SimdError};
+use std::{convert::TryFrom, io};
 
-/// All bytes are encoded with the multibase `base64` w/o padding, resulting
-/// in the prefix `"m"`.
+/// All bytes are encoded as standard `base64` w/o padding (and, unlike
+/// multibase, w/o the prefix `"m"`).
 pub const DEFAULT_MB: Multibase = Multibase::Base64;
 
-// TODO: add support for simd-json
-#[cfg(not(feature = "simd"))]
+/// The key of the single-entry maps used to represent bytes and links.
+pub const LINK_KEY: &str = "/";
+
+/// The start of the message of an `Error::InvalidLinkString`, preceding its
+/// CID string.
+const INVALID_LINK_STRING: &str = "Invalid link: invalid CID string `";
+
 /// The [DagJSON](https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-json.md) codec, that delegates to `serde_json`.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct DagJson;
@@ -38,6 +44,7 @@ impl TryFrom<u64> for DagJson {
     }
 }
 
+#[async_trait]
 impl Codec for DagJson {
     const CODE: u64 = 0x0129;
 
@@ -53,7 +60,7 @@ impl Codec for DagJson {
     where
         T: Representation + Deserialize<'de>,
     {
-        from_slice(bytes).map_err(|e| Error::Decoder(anyhow::Error::new(e)))
+        from_slice(bytes).map_err(decode_error)
     }
 
     fn read<T, R>(reader: R) -> Result<T, Error>
@@ -61,19 +68,119 @@ impl Codec for DagJson {
         T: Representation + for<'de> Deserialize<'de>,
         R: Read,
     {
-        from_reader(reader).map_err(|e| Error::Decoder(anyhow::Error::new(e)))
+        from_reader(reader).map_err(decode_error)
+    }
+
+    /// Reads the bytes of a single JSON value as they arrive, without reading
+    /// past its end (except for the byte following a top-level number), so that
+    /// further values can be read from the same reader, then decodes them.
+    ///
+    /// As values are read a byte at a time, the reader should be buffered.
+    async fn read_async<T, R>(mut reader: R) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+        R: AsyncRead + Unpin + Send,
+    {
+        let bytes = read_value(&mut reader)
+            .await
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        Self::decode(&bytes)
     }
 }
 
+/// Reads exactly the bytes of one JSON value, skipping any leading whitespace.
+async fn read_value<R>(mut reader: R) -> io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    let mut byte = [0];
+    let first = loop {
+        reader.read_exact(&mut byte).await?;
+        if !byte[0].is_ascii_whitespace() {
+            break byte[0];
+        }
+    };
+
+    let mut bytes = vec![first];
+    match first {
+        // `true`, `false` and `null`
+        b't' | b'f' | b'n' => {
+            let len = if first == b'f' { 4 } else { 3 };
+            let read = (&mut reader).take(len).read_to_end(&mut bytes).await?;
+            if read as u64 != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        // numbers end at the first non-numeric byte, or the end of the input
+        b'-' | b'0'..=b'9' => {
+            while reader.read(&mut byte).await? == 1 {
+                match byte[0] {
+                    b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E' => bytes.push(byte[0]),
+                    _ => break,
+                }
+            }
+        }
+        // strings, lists and maps end at the matching (unquoted) delimiter
+        _ => {
+            let (mut depth, mut in_str, mut escaped) = (0usize, false, false);
+            let mut next = first;
+            loop {
+                match next {
+                    _ if escaped => escaped = false,
+                    b'\\' if in_str => escaped = true,
+                    b'"' => in_str = !in_str,
+                    _ if in_str => {}
+                    b'[' | b'{' => depth += 1,
+                    b']' | b'}' => {
+                        depth = depth.checked_sub(1).ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::InvalidData, "unexpected delimiter")
+                        })?
+                    }
+                    _ => {}
+                }
+                if depth == 0 && !in_str {
+                    break;
+                }
+
+                reader.read_exact(&mut byte).await?;
+                next = byte[0];
+                bytes.push(next);
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Maps a decoding error to an `Error`, recovering the typed error of any
+/// invalid link string that caused it.
+///
+/// `serde_json` and `simd-json` errors only retain the message of a custom
+/// error, so the typed error is rebuilt from the CID string within the message.
+fn decode_error<E>(err: E) -> Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let msg = err.to_string();
+    let link_err = msg.find(INVALID_LINK_STRING).and_then(|start| {
+        let cid_str = msg[start + INVALID_LINK_STRING.len()..].split('`').next()?;
+        let source = Cid::try_from(cid_str).err()?;
+        Some(Error::InvalidLinkString {
+            cid_str: cid_str.into(),
+            source,
+        })
+    });
+    link_err.unwrap_or_else(|| Error::Decoder(anyhow::Error::new(err)))
+}
+
 impl<'a, W: Write> Encoder for &'a mut JsonSerializer<W> {
     /// Serializes bytes as a struct variant, e.g.
-    /// `{ "/": { "base64": <some base64-encoded string> } }`.
+    /// `{ "/": { "bytes": <some unpadded base64-encoded string> } }`.
     #[inline]
     fn serialize_bytes(self, bytes: &[u8]) -> Result<Self::Ok, JsonError> {
         use ser::SerializeStructVariant as SV;
 
-        let mut sv = self.serialize_struct_variant("", 0, "/", 1)?;
-        SV::serialize_field(&mut sv, "bytes", &multibase::encode(DEFAULT_MB, bytes))?;
+        let mut sv = self.serialize_struct_variant("", 0, LINK_KEY, 1)?;
+        SV::serialize_field(&mut sv, "bytes", &DEFAULT_MB.encode(bytes))?;
         SV::end(sv)
     }
 
@@ -88,7 +195,7 @@ impl<'a, W: Write> Encoder for &'a mut JsonSerializer<W> {
             cid::Version::V1 => Multibase::Base32Lower,
         };
         let cid_str = multibase::encode(mb, cid.to_bytes());
-        self.serialize_newtype_variant("", 0, "/", &cid_str)
+        self.serialize_newtype_variant("", 0, LINK_KEY, &cid_str)
     }
 }
 
@@ -97,7 +204,7 @@ impl<'de, 'a, R: JsonRead<'de>> Decoder<'de> for &'a mut JsonDeserializer<R> {
     /// in the Serde data model:
     ///     - maps
     ///     - Base58Btc- or Base32Lower-encoded links, e.g. `{ "/": "Qm..." }`
-    ///     - Base64-encoded byte sequences, e.g. `{ "/": { "bytes": "m..." } }`
+    ///     - Base64-encoded byte sequences, e.g. `{ "/": { "bytes": "..." } }`
     ///
     /// This method wraps the provided `Visitor`, delegating the visiting of all
     /// types found in the input data to the provided `Visitor` (except for maps,
@@ -135,6 +242,153 @@ impl<'de, 'a, R: JsonRead<'de>> Decoder<'de> for &'a mut JsonDeserializer<R> {
     }
 }
 
+/// The [DagJSON](https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-json.md) codec, that delegates decoding to `simd-json`.
+///
+/// Encoding and decoding borrowed dags from immutable bytes are identical to
+/// (and delegate to) `DagJson`.
+#[cfg(feature = "simd")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SimdDagJson;
+
+#[cfg(feature = "simd")]
+impl Into<u64> for SimdDagJson {
+    fn into(self) -> u64 {
+        Self::CODE
+    }
+}
+
+#[cfg(feature = "simd")]
+impl TryFrom<u64> for SimdDagJson {
+    type Error = Error;
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        match code {
+            Self::CODE => Ok(Self),
+            _ => Err(Error::UnknownCodec(code)),
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+#[async_trait]
+impl Codec for SimdDagJson {
+    const CODE: u64 = DagJson::CODE;
+
+    fn write<T, W>(dag: &T, writer: W) -> Result<(), Error>
+    where
+        T: Representation + Serialize,
+        W: Write,
+    {
+        DagJson::write(dag, writer)
+    }
+
+    /// `simd-json` parses in-place, and therefore cannot borrow from immutable
+    /// bytes, so decoding borrowed dags delegates to `DagJson` (see
+    /// `SimdDagJson::decode_mut` for decoding borrowed dags with `simd-json`).
+    fn decode<'de, T>(bytes: &'de [u8]) -> Result<T, Error>
+    where
+        T: Representation + Deserialize<'de>,
+    {
+        DagJson::decode(bytes)
+    }
+
+    /// Copies the bytes into a buffer and deserializes from it with
+    /// `simd-json`.
+    fn decode_owned<T>(bytes: &[u8]) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+    {
+        let mut bytes = bytes.to_vec();
+        Self::decode_mut(&mut bytes)
+    }
+
+    /// Reads the bytes into a buffer and deserializes from it with
+    /// `simd-json`.
+    fn read<T, R>(mut reader: R) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+        R: Read,
+    {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        Self::decode_mut(&mut bytes)
+    }
+
+    /// Reads a single JSON value identically to `DagJson::read_async`, then
+    /// deserializes it with `simd-json`.
+    async fn read_async<T, R>(mut reader: R) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+        R: AsyncRead + Unpin + Send,
+    {
+        let mut bytes = read_value(&mut reader)
+            .await
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        Self::decode_mut(&mut bytes)
+    }
+}
+
+#[cfg(feature = "simd")]
+impl SimdDagJson {
+    /// Given some mutable bytes, deserialize a dag with `simd-json`, which
+    /// parses (and therefore modifies) the bytes in-place.
+    pub fn decode_mut<'de, T>(bytes: &'de mut [u8]) -> Result<T, Error>
+    where
+        T: Representation + Deserialize<'de>,
+    {
+        let mut de = SimdDeserializer::from_slice(bytes)
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        T::deserialize(&mut de).map_err(decode_error)
+    }
+}
+
+/// Implements `Decoder` for `simd-json`'s deserializers, identically to the
+/// `serde_json` implementation.
+#[cfg(feature = "simd")]
+macro_rules! impl_simd_decoder {
+    ($(impl<$($lt:lifetime),*> for $type:ty;)*) => {$(
+        impl<$($lt),*> Decoder<'de> for $type {
+            #[inline]
+            fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, SimdError>
+            where
+                V: IpldVisitorExt<'de>,
+            {
+                Deserializer::deserialize_any(self, JsonVisitor(visitor))
+            }
+
+            #[inline]
+            fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, SimdError>
+            where
+                V: IpldVisitorExt<'de>,
+            {
+                Decoder::deserialize_byte_buf(self, visitor)
+            }
+
+            #[inline]
+            fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, SimdError>
+            where
+                V: IpldVisitorExt<'de>,
+            {
+                Deserializer::deserialize_map(self, JsonVisitor(visitor))
+            }
+
+            #[inline]
+            fn deserialize_link<V>(self, visitor: V) -> Result<V::Value, SimdError>
+            where
+                V: IpldVisitorExt<'de>,
+            {
+                Deserializer::deserialize_map(self, JsonVisitor(visitor))
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "simd")]
+impl_simd_decoder! {
+    impl<'de, 'a> for &'a mut SimdDeserializer<'de>;
+}
+
 /// `JsonVisitor` wraps an "any" type `Visitor` in order to enhance how maps are
 /// deserialized.
 struct JsonVisitor<V>(V);
@@ -155,10 +409,18 @@ impl<'de, V: IpldVisitorExt<'de>> Visitor<'de> for JsonVisitor<V> {
         A: de::MapAccess<'de>,
     {
         let first_key: Option<String> = map.next_key()?;
-        if let Some("/") = first_key.as_deref() {
-            match map.next_value::<MapLikeVisitor>() {
-                Ok(MapLikeVisitor::Bytes(b)) => self.0.visit_byte_buf(b),
-                Ok(MapLikeVisitor::Cid(b)) => self.0.visit_link(b),
+        if first_key.as_deref() == Some(LINK_KEY) {
+            let map_like = map.next_value::<MapLikeVisitor>()?;
+            if let Some(key) = map.next_key::<String>()? {
+                return Err(de::Error::custom(format!(
+                    "unexpected key `{}` in a `{}` map",
+                    key, LINK_KEY
+                )));
+            }
+
+            match map_like {
+                MapLikeVisitor::Bytes(b) => self.0.visit_byte_buf(b),
+                MapLikeVisitor::Cid(b) => self.0.visit_link(b),
                 _ => Err(de::Error::custom("expected a CID or byte string")),
             }
         } else {
@@ -219,7 +481,7 @@ impl<'de> Deserialize<'de> for MapLikeVisitor {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_map(MapLikeVisitor::Default)
+        Deserializer::deserialize_any(deserializer, MapLikeVisitor::Default)
     }
 }
 
@@ -231,95 +493,189 @@ impl<'de> Visitor<'de> for MapLikeVisitor {
         formatter.write_str("a JSON map, link object or byte object")
     }
 
-    /// TODO:
-    /// In the dag-json codec, links are represented as either Base58 or Base32
-    /// strings
+    /// In the dag-json codec, links are represented as strings, either as
+    /// a Base58Btc-encoded CIDv0 (e.g. `"Qm..."`) or as a multibase-encoded
+    /// CIDv1 (typically Base32Lower, e.g. `"bafy..."`).
     #[inline]
     fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        // TODO
-        // let cid = ToCid::to_cid(s).map_err(|_| de::Error::custom("expected a CID"))?;
-        // Ok(MapLikeVisitor::Cid(cid))
-        unimplemented!()
+        let cid = Cid::try_from(s).map_err(|source| {
+            E::custom(Error::InvalidLinkString {
+                cid_str: s.into(),
+                source,
+            })
+        })?;
+        Ok(MapLikeVisitor::Cid(cid.to_bytes().into()))
     }
 
-    /// TODO:
     /// In the dag-json codec, bytes are represented as maps, with the key
     /// always being the string "bytes" and the value always being the bytes
-    /// multibase-encoded as a string.
+    /// encoded as an unpadded, unprefixed base64 string.
     #[inline]
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where
         A: de::MapAccess<'de>,
     {
         // TODO: why do these have to be Strings instead of &str?
-        let (base, byte_str): (String, String) = map.next_entry()?.ok_or_else(|| {
-            de::Error::custom("expected a base string + multibase-encoded string key-value pair")
+        let (key, byte_str): (String, String) = map.next_entry()?.ok_or_else(|| {
+            de::Error::custom("expected a `bytes` + base64-encoded string key-value pair")
         })?;
 
-        if base.as_str() != "bytes" {
-            return Err(de::Error::custom(
-                "DagJSON only supports base64-encoded strings",
-            ));
+        if key.as_str() != "bytes" {
+            return Err(de::Error::custom(format!(
+                "unexpected key `{}` in a bytes map",
+                key
+            )));
         }
-
-        let (mb, bytes) = multibase::decode(byte_str)
-            .map_err(|_| de::Error::custom("expected a base64 multibase-encoded string"))?;
-
-        match mb {
-            DEFAULT_MB => Ok(MapLikeVisitor::Bytes(bytes)),
-            _ => Err(de::Error::custom(
-                "DagJSON only supports base64-encoded strings",
-            )),
+        if let Some(key) = map.next_key::<String>()? {
+            return Err(de::Error::custom(format!(
+                "unexpected key `{}` in a bytes map",
+                key
+            )));
         }
+
+        decode_bytes(&byte_str)
+            .map(MapLikeVisitor::Bytes)
+            .ok_or_else(|| {
+                de::Error::custom(format!(
+                    "expected an unpadded base64-encoded string, found `{}`",
+                    byte_str
+                ))
+            })
     }
 }
 
-/// Wraps a `MapAccess` thats had it's first key removed.
-struct MapAccessor<A> {
-    first_key: Option<String>,
-    map: A,
+/// Decodes an unpadded, unprefixed base64 string.
+#[inline]
+fn decode_bytes(byte_str: &str) -> Option<Vec<u8>> {
+    DEFAULT_MB.decode(byte_str).ok()
+}
+
+impl DagJson {
+    /// Given some bytes, deserialize a dag, additionally accepting bytes in
+    /// the legacy multibase-prefixed form (i.e. `{"/": {"bytes": "m..."}}`).
+    ///
+    /// The legacy form is preferred, as many legacy strings are also valid
+    /// unprefixed base64. Since legacy bytes are first upgraded to the current
+    /// form within a copy of the input, only owned dags can be decoded.
+    pub fn decode_legacy<T>(bytes: &[u8]) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+    {
+        let LegacyJson(value) =
+            from_slice(bytes).map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        let bytes =
+            serde_json::to_vec(&value).map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        Self::decode(&bytes)
+    }
 }
 
-impl<'de, A: de::MapAccess<'de>> de::MapAccess<'de> for MapAccessor<A> {
-    type Error = A::Error;
+/// A JSON value whose legacy multibase-prefixed bytes have been upgraded to
+/// the current unprefixed form.
+struct LegacyJson(JsonValue);
 
+impl<'de> Deserialize<'de> for LegacyJson {
     #[inline]
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
-        K: de::DeserializeSeed<'de>,
+        D: Deserializer<'de>,
     {
-        use de::IntoDeserializer;
+        deserializer.deserialize_any(LegacyJsonVisitor)
+    }
+}
 
-        if self.first_key.is_some() {
-            let first_key = self.first_key.take().unwrap();
-            seed.deserialize(first_key.into_deserializer()).map(Some)
-        } else {
-            self.map.next_key_seed(seed)
+struct LegacyJsonVisitor;
+
+impl<'de> Visitor<'de> for LegacyJsonVisitor {
+    type Value = LegacyJson;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Self::Value, E> {
+        Ok(LegacyJson(JsonValue::Bool(b)))
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Self::Value, E> {
+        Ok(LegacyJson(JsonValue::from(n)))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Self::Value, E> {
+        Ok(LegacyJson(JsonValue::from(n)))
+    }
+
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Self::Value, E> {
+        Ok(LegacyJson(JsonValue::from(n)))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(LegacyJson(JsonValue::String(s.into())))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(LegacyJson(JsonValue::Null))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut list = Vec::new();
+        while let Some(LegacyJson(value)) = seq.next_element()? {
+            list.push(value);
         }
+        Ok(LegacyJson(JsonValue::Array(list)))
     }
 
-    delegate! {
-        to self.map {
-            fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
-            where
-                V: de::DeserializeSeed<'de>;
+    /// Collects the map (rejecting duplicate keys, as they would otherwise be
+    /// silently dropped), upgrading it if it is a legacy bytes map.
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut object = JsonMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let LegacyJson(value) = map.next_value()?;
+            if object.contains_key(&key) {
+                return Err(de::Error::custom(format!("duplicate map key `{}`", key)));
+            }
+            object.insert(key, value);
+        }
+
+        if object.len() == 1 {
+            if let Some(JsonValue::Object(inner)) = object.get_mut(LINK_KEY) {
+                if let (1, Some(JsonValue::String(byte_str))) =
+                    (inner.len(), inner.get_mut("bytes"))
+                {
+                    if let Ok((DEFAULT_MB, bytes)) = multibase::decode(byte_str.as_str()) {
+                        *byte_str = DEFAULT_MB.encode(bytes);
+                    }
+                }
+            }
         }
+        Ok(LegacyJson(JsonValue::Object(object)))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{_codecs::test_utils::*, prelude::*};
+    use crate::{
+        _codecs::{test_schemas::*, test_utils::*},
+        prelude::*,
+    };
     use std::str::FromStr;
 
     fn roundtrip<'de, T>(cases: &[(T, &'de str)])
     where
         T: PartialEq + Debug + Representation + Serialize + DeserializeOwned,
     {
-        roundtrip_str_codec::<DagJson, T>(cases)
+        roundtrip_str_codec::<DagJson, T>(cases);
+        #[cfg(feature = "simd")]
+        roundtrip_str_codec::<SimdDagJson, T>(cases);
     }
 
     #[test]
@@ -352,13 +708,60 @@ mod tests {
         roundtrip(tests);
     }
 
+    #[test]
+    fn test_borrowed() {
+        let json = br#""hello world""#;
+        let s = DagJson::decode::<&str>(json).unwrap();
+        assert_eq!(s, "hello world");
+        assert!(json.as_ptr_range().contains(&s.as_ptr()));
+
+        #[cfg(feature = "simd")]
+        {
+            let s = SimdDagJson::decode::<&str>(json).unwrap();
+            assert_eq!(s, "hello world");
+            assert!(json.as_ptr_range().contains(&s.as_ptr()));
+
+            let mut json = json.to_vec();
+            let range = json.as_ptr_range();
+            let s = SimdDagJson::decode_mut::<&str>(&mut json).unwrap();
+            assert_eq!(s, "hello world");
+            assert!(range.contains(&s.as_ptr()));
+        }
+    }
+
     #[test]
     fn test_bytes() {
         let tests = &[(
             Bytes::from(vec![0x01, 0x02, 0x03]),
-            r#"{"/":{"bytes":"mAQID"}}"#,
+            r#"{"/":{"bytes":"AQID"}}"#,
         )];
         roundtrip(tests);
+
+        let json = r#"{"/":{"bytes":"AQID","foo":1}}"#;
+        assert!(DagJson::decode::<Bytes>(json.as_bytes()).is_err());
+        let json = r#"{"/":{"bytes":"AQID"},"foo":1}"#;
+        assert!(DagJson::decode::<Bytes>(json.as_bytes()).is_err());
+        let json = r#"{"/":{"bytes":"AQI="}}"#;
+        assert!(DagJson::decode::<Bytes>(json.as_bytes()).is_err());
+
+        // legacy multibase-prefixed bytes
+        let json = br#"{"/":{"bytes":"mAQID"}}"#;
+        assert!(DagJson::decode::<Bytes>(json).is_err());
+        let bytes = DagJson::decode_legacy::<Bytes>(json).unwrap();
+        assert_eq!(bytes, Bytes::from(vec![0x01, 0x02, 0x03]));
+        let json = br#"[{"/":{"bytes":"AQID"}},{"/":{"bytes":"mAQID"}}]"#;
+        let list = DagJson::decode_legacy::<Vec<Bytes>>(json).unwrap();
+        assert_eq!(list, vec![Bytes::from(vec![0x01, 0x02, 0x03]); 2]);
+
+        // legacy bytes that are also valid unprefixed base64
+        let json = br#"{"/":{"bytes":"mAQI"}}"#;
+        let bytes = DagJson::decode::<Bytes>(json).unwrap();
+        assert_eq!(bytes, Bytes::from(vec![0x98, 0x04, 0x08]));
+        let bytes = DagJson::decode_legacy::<Bytes>(json).unwrap();
+        assert_eq!(bytes, Bytes::from(vec![0x01, 0x02]));
+
+        let json = br#"{"a":1,"a":2}"#;
+        assert!(DagJson::decode_legacy::<std::collections::BTreeMap<String, Int>>(json).is_err());
     }
 
     #[test]
@@ -372,8 +775,298 @@ mod tests {
     }
 
     #[test]
-    fn test_seq() {}
+    fn test_link_v1() {
+        let s = String::from("bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily");
+        let cid = Cid::from_str(&s).unwrap();
+        let json = format!("{{\"/\":\"{}\"}}", s);
+
+        let tests = &[(Link::<Null>::from(cid.clone()), json.as_str())];
+        roundtrip(tests);
+
+        // CIDv1s can be encoded in any multibase
+        let b64 = cid.to_string_of_base(Multibase::Base64).unwrap();
+        let json = format!("{{\"/\":\"{}\"}}", b64);
+        let link: Link<Null> = DagJson::decode(json.as_bytes()).unwrap();
+        assert_eq!(link, Link::from(cid));
+    }
+
+    #[test]
+    fn test_invalid_link() {
+        let json = r#"{"/":"Qmfoo"}"#;
+        let err = DagJson::decode::<Link<Null>>(json.as_bytes()).unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidLinkString { cid_str, .. } if cid_str == "Qmfoo"),
+            "{}",
+            err
+        );
+        let err = DagJson::read::<Link<Null>, _>(json.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::InvalidLinkString { .. }), "{}", err);
+        #[cfg(feature = "simd")]
+        {
+            let err = SimdDagJson::decode_owned::<Link<Null>>(json.as_bytes()).unwrap_err();
+            assert!(matches!(err, Error::InvalidLinkString { .. }), "{}", err);
+        }
+
+        let json = r#"{"/":123}"#;
+        assert!(DagJson::decode::<Link<Null>>(json.as_bytes()).is_err());
+
+        let json = r#"{"/":"QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n","foo":1}"#;
+        assert!(DagJson::decode::<Link<Null>>(json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_seq() {
+        let s = "bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily";
+        let link = || Link::<Null>::from(Cid::from_str(s).unwrap());
+        let json = format!("[{{\"/\":\"{0}\"}},{{\"/\":\"{0}\"}}]", s);
+
+        let tests = &[(vec![link(), link()], json.as_str())];
+        roundtrip(tests);
+
+        let tests = &[(Names(vec!["a".into(), "b".into()]), r#"["a","b"]"#)];
+        roundtrip(tests);
+        let tests = &[(NullableInts(vec![Some(Int::from(1)), None]), "[1,null]")];
+        roundtrip(tests);
+    }
+
+    #[test]
+    fn test_map() {
+        let s = "bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily";
+        let cid = Cid::from_str(s).unwrap();
+        let json = format!("{{\"link\":{{\"/\":\"{}\"}}}}", s);
+
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(String::from("link"), Link::<Null>::from(cid.clone()));
+        let tests = &[(map, json.as_str())];
+        roundtrip(tests);
+
+        let tests = &[(
+            LinkStruct {
+                link: Link::from(cid),
+            },
+            json.as_str(),
+        )];
+        roundtrip(tests);
+
+        let map = || {
+            let mut map = std::collections::BTreeMap::new();
+            map.insert(String::from("a"), Int::from(1));
+            map.insert(String::from("bb"), Int::from(2));
+            map
+        };
+
+        let tests = &[(Counts(map()), r#"{"a":1,"bb":2}"#)];
+        roundtrip(tests);
+        let tests = &[(PairCounts(map()), r#"[["a",1],["bb",2]]"#)];
+        roundtrip(tests);
+        let tests = &[(QueryCounts(map()), r#""a=1&bb=2""#)];
+        roundtrip(tests);
+
+        // delimiters within keys or values are rejected
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(String::from("a&b"), Int::from(1));
+        assert!(DagJson::write(&QueryCounts(map), Vec::new()).is_err());
+        assert!(DagJson::decode::<QueryCounts>(br#""a=1&b""#).is_err());
+
+        // duplicate keys are rejected
+        let json = br#"[["a",1],["a",2]]"#;
+        assert!(DagJson::decode::<PairCounts>(json).is_err());
+        assert!(DagJson::decode::<QueryCounts>(br#""a=1&a=2""#).is_err());
+    }
+
+    #[test]
+    fn test_struct() {
+        let point = |x: i32, label: Option<&str>| Point {
+            x: Int::from(x),
+            y: Int::from(2),
+            label: label.map(String::from),
+        };
+
+        // fields are listed in `fieldOrder`, omitting absent trailing fields
+        let tests = &[
+            (point(1, Some("a")), r#"[2,1,"a"]"#),
+            (point(1, None), "[2,1]"),
+        ];
+        roundtrip(tests);
+
+        assert!(DagJson::decode::<Point>(b"[2]").is_err());
+        assert!(DagJson::decode::<Point>(br#"[2,1,"a",3]"#).is_err());
+        assert!(DagJson::decode::<Point>(br#"{"x":1,"y":2}"#).is_err());
+
+        // an absent optional nullable field is distinct from a null one
+        let range = |end: Option<Option<i32>>, step: Option<i32>| Range {
+            start: Int::from(1),
+            end: end.map(|end| end.map(Int::from)),
+            step: step.map(Int::from),
+        };
+        let tests = &[
+            (range(None, None), "[1]"),
+            (range(Some(Some(5)), None), "[1,5]"),
+            (range(Some(None), None), "[1,null]"),
+            (range(Some(None), Some(2)), "[1,null,2]"),
+        ];
+        roundtrip(tests);
+        assert!(DagJson::write(&range(None, Some(2)), Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_struct_stringjoin() {
+        let version = |major: i32, minor: i32| Version {
+            major: Int::from(major),
+            minor: Int::from(minor),
+        };
+        let tests = &[(version(1, 20), r#""1.20""#)];
+        roundtrip(tests);
+        let err = DagJson::decode::<Version>(br#""1.x""#).unwrap_err();
+        assert!(err.to_string().contains("`minor`"), "{}", err);
+        assert!(DagJson::decode::<Version>(br#""1.2.3""#).is_err());
+    }
+
+    #[test]
+    fn test_struct_stringpairs() {
+        // implicit values are omitted
+        let query = |limit: i32, verbose: Option<bool>| Query {
+            name: "a b".into(),
+            limit: Int::from(limit),
+            verbose,
+        };
+        let tests = &[
+            (query(5, Some(true)), r#""n=a b,limit=5,verbose=true""#),
+            (query(10, None), r#""n=a b""#),
+        ];
+        roundtrip(tests);
+        let query = DagJson::decode::<Query>(br#""limit=5,n=a b""#).unwrap();
+        assert_eq!(query.limit, Int::from(5));
+        let err = DagJson::write(
+            &Query {
+                name: "a,b".into(),
+                ..query
+            },
+            Vec::new(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("`name`"), "{}", err);
+        assert!(DagJson::decode::<Query>(br#""n=a,other=1""#).is_err());
+        assert!(DagJson::decode::<Query>(br#""limit=5""#).is_err());
+        assert!(DagJson::decode::<Query>(br#""n=a=b""#).is_err());
+
+        // implicit values are compared once parsed
+        let zoom = |level: f64| Zoom {
+            level: Float::from(level),
+        };
+        let tests = &[(zoom(1.0), r#""""#), (zoom(1.5), r#""level=1.5""#)];
+        roundtrip(tests);
+    }
+
+    #[test]
+    fn test_struct_listpairs() {
+        let entry = |size: Option<i32>, count: i32| Entry {
+            name: "a".into(),
+            size: size.map(Int::from),
+            count: Int::from(count),
+        };
+        let tests = &[
+            (entry(Some(3), 1), r#"[["Name","a"],["size",3]]"#),
+            (entry(None, 1), r#"[["Name","a"]]"#),
+            (entry(None, 2), r#"[["Name","a"],["count",2]]"#),
+        ];
+        roundtrip(tests);
+
+        // pairs may be in any order, and implicit values may be present
+        let json = br#"[["size",3],["Name","a"]]"#;
+        assert_eq!(DagJson::decode::<Entry>(json).unwrap(), entry(Some(3), 1));
+        let json = br#"[["count",1],["Name","a"]]"#;
+        assert_eq!(DagJson::decode::<Entry>(json).unwrap(), entry(None, 1));
+        let json = br#"[["Name","a"],["other",1]]"#;
+        assert!(DagJson::decode::<Entry>(json).is_err());
+        let json = br#"[["Name","a"],["Name","b"]]"#;
+        assert!(DagJson::decode::<Entry>(json).is_err());
+        assert!(DagJson::decode::<Entry>(br#"[["count",1]]"#).is_err());
+    }
+
+    #[test]
+    fn test_union() {
+        let circle = || Circle {
+            radius: Int::from(1),
+        };
+        let square = || Square { side: Int::from(2) };
+
+        let tests = &[
+            (
+                Shape::Circle(circle()),
+                r#"{"tag":"circle","content":{"radius":1}}"#,
+            ),
+            (
+                Shape::Square(square()),
+                r#"{"tag":"square","content":{"side":2}}"#,
+            ),
+        ];
+        roundtrip(tests);
+
+        // the content may precede the discriminant
+        let json = br#"{"content":{"side":2},"tag":"square"}"#;
+        let shape = DagJson::decode::<Shape>(json).unwrap();
+        assert_eq!(shape, Shape::Square(square()));
+        let json = br#"{"tag":"triangle","content":{"side":2}}"#;
+        assert!(DagJson::decode::<Shape>(json).is_err());
+        assert!(DagJson::decode::<Shape>(br#"{"tag":"square"}"#).is_err());
+
+        // the discriminant is merged into the member's map
+        let tests = &[
+            (
+                InlineShape::Circle(circle()),
+                r#"{"radius":1,"tag":"circle"}"#,
+            ),
+            (
+                InlineShape::Square(square()),
+                r#"{"side":2,"tag":"square"}"#,
+            ),
+        ];
+        roundtrip(tests);
+
+        let json = br#"{"tag":"square","side":2}"#;
+        let shape = DagJson::decode::<InlineShape>(json).unwrap();
+        assert_eq!(shape, InlineShape::Square(square()));
+        assert!(DagJson::decode::<InlineShape>(br#"{"side":2}"#).is_err());
+        let json = br#"{"tag":"circle","side":2}"#;
+        assert!(DagJson::decode::<InlineShape>(json).is_err());
+        let json = br#"{"tag":"circle","radius":1,"radius":2}"#;
+        assert!(DagJson::decode::<InlineShape>(json).is_err());
+    }
 
     #[test]
-    fn test_map() {}
+    fn test_async() {
+        use futures::{executor::block_on, io::Cursor};
+
+        // values are read one at a time from the same reader
+        let json = br#" [1,"]"] {"a":"\"}","b":{"c":[]}} "\\" true -1.5e3 null"#;
+        let mut reader = Cursor::new(&json[..]);
+        let list: Value = block_on(DagJson::read_async(&mut reader)).unwrap();
+        assert_eq!(list, Value::List(vec![1u8.into(), "]".into()]));
+        assert_eq!(reader.position(), 8);
+        let map: Value = block_on(DagJson::read_async(&mut reader)).unwrap();
+        assert_eq!(map["a"].as_str(), Some("\"}"));
+        assert_eq!(reader.position(), 33);
+        let s: String = block_on(DagJson::read_async(&mut reader)).unwrap();
+        assert_eq!(s, "\\");
+        let b: bool = block_on(DagJson::read_async(&mut reader)).unwrap();
+        assert!(b);
+        let f: Float = block_on(DagJson::read_async(&mut reader)).unwrap();
+        assert_eq!(f, Float::from(-1500.0));
+        assert!(block_on(DagJson::read_async::<(), _>(&mut reader)).is_ok());
+        assert!(block_on(DagJson::read_async::<(), _>(&mut reader)).is_err());
+
+        // truncated values
+        let json = br#"{"a":[1"#;
+        let res = block_on(DagJson::read_async::<Value, _>(Cursor::new(&json[..])));
+        assert!(res.is_err());
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        type LinkStruct struct {
+            link Link<Null>,
+        };
+    }
 }
@@ -0,0 +1,387 @@
+//! IPLD DagPb codec.
+
+use crate::dev::*;
+use anyhow::anyhow;
+use std::convert::TryFrom;
+
+schema! {
+    /// A [DagPB](https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-pb.md)
+    /// node, the only type representable by the `DagPb` codec.
+    #[ipld_attr(internal)]
+    #[derive(Debug, PartialEq)]
+    pub type PbNode struct {
+        pub links Vec<PbLink> (rename "Links"),
+        pub data optional Bytes (rename "Data"),
+    };
+}
+
+schema! {
+    /// A link within a `PbNode`.
+    #[ipld_attr(internal)]
+    #[derive(Debug, PartialEq)]
+    pub type PbLink struct {
+        pub hash Link<PbNode> (rename "Hash"),
+        pub name optional String (rename "Name"),
+        pub tsize optional u64 (rename "Tsize"),
+    };
+}
+
+/// The [DagPB](https://github.com/ipld/specs/blob/master/block-layer/codecs/dag-pb.md) codec.
+///
+/// Only `PbNode`s can be encoded or decoded, and nodes are decoded strictly
+/// (i.e. fields must appear in the order required by the spec, exactly once).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DagPb;
+
+impl Into<u64> for DagPb {
+    fn into(self) -> u64 {
+        Self::CODE
+    }
+}
+
+impl TryFrom<u64> for DagPb {
+    type Error = Error;
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        match code {
+            Self::CODE => Ok(Self),
+            _ => Err(Error::UnknownCodec(code)),
+        }
+    }
+}
+
+impl Codec for DagPb {
+    const CODE: u64 = 0x70;
+
+    fn write<T, W>(dag: &T, mut writer: W) -> Result<(), Error>
+    where
+        T: Representation + Serialize,
+        W: Write,
+    {
+        let mut bytes = Vec::new();
+        encode_node(dag.as_pb_node()?, &mut bytes)?;
+        writer
+            .write_all(&bytes)
+            .map_err(|e| Error::Encoder(anyhow::Error::new(e)))
+    }
+
+    fn decode<'de, T>(bytes: &'de [u8]) -> Result<T, Error>
+    where
+        T: Representation + Deserialize<'de>,
+    {
+        T::from_pb_node(decode_node(bytes)?)
+    }
+
+    fn read<T, R>(mut reader: R) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+        R: Read,
+    {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        Self::decode(&bytes)
+    }
+}
+
+/// Converts dags to and from `PbNode`s, the only type supported by `DagPb`.
+trait PbRepresentation: Sized {
+    fn as_pb_node(&self) -> Result<&PbNode, Error>;
+    fn from_pb_node(node: PbNode) -> Result<Self, Error>;
+}
+
+/// Default (specialized) implementation, which rejects all non-`PbNode` types.
+impl<T: Representation> PbRepresentation for T {
+    #[inline]
+    default fn as_pb_node(&self) -> Result<&PbNode, Error> {
+        Err(Error::Encoder(anyhow!(
+            "DagPb can only encode `PbNode`s, not `{}`",
+            T::NAME
+        )))
+    }
+
+    #[inline]
+    default fn from_pb_node(_: PbNode) -> Result<Self, Error> {
+        Err(Error::Decoder(anyhow!(
+            "DagPb can only decode `PbNode`s, not `{}`",
+            T::NAME
+        )))
+    }
+}
+
+impl PbRepresentation for PbNode {
+    #[inline]
+    fn as_pb_node(&self) -> Result<&PbNode, Error> {
+        Ok(self)
+    }
+
+    #[inline]
+    fn from_pb_node(node: PbNode) -> Result<Self, Error> {
+        Ok(node)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// protobuf encoding
+////////////////////////////////////////////////////////////////////////////////
+
+const WIRE_TYPE_VARINT: u64 = 0;
+const WIRE_TYPE_LEN: u64 = 2;
+
+const NODE_DATA: u64 = 1;
+const NODE_LINKS: u64 = 2;
+const LINK_HASH: u64 = 1;
+const LINK_NAME: u64 = 2;
+const LINK_TSIZE: u64 = 3;
+
+/// Encodes a `PbNode`, writing its links before its data.
+fn encode_node(node: &PbNode, buf: &mut Vec<u8>) -> Result<(), Error> {
+    let mut link_buf = Vec::new();
+    let mut prev_name: Option<&[u8]> = None;
+    for link in node.links.iter() {
+        let name = link.name.as_ref().map(String::as_bytes).unwrap_or_default();
+        if prev_name.map_or(false, |prev_name| prev_name > name) {
+            return Err(Error::Encoder(anyhow!(
+                "DagPb links must be sorted by name"
+            )));
+        }
+        prev_name = Some(name);
+
+        link_buf.clear();
+        encode_link(link, &mut link_buf);
+        write_len_field(buf, NODE_LINKS, &link_buf);
+    }
+
+    if let Some(data) = node.data.as_ref() {
+        write_len_field(buf, NODE_DATA, AsRef::<[u8]>::as_ref(data));
+    }
+    Ok(())
+}
+
+fn encode_link(link: &PbLink, buf: &mut Vec<u8>) {
+    write_len_field(buf, LINK_HASH, &link.hash.cid().to_bytes());
+    if let Some(name) = link.name.as_ref() {
+        write_len_field(buf, LINK_NAME, name.as_bytes());
+    }
+    if let Some(tsize) = link.tsize {
+        write_varint(buf, LINK_TSIZE << 3 | WIRE_TYPE_VARINT);
+        write_varint(buf, tsize);
+    }
+}
+
+fn write_len_field(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_varint(buf, field << 3 | WIRE_TYPE_LEN);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// protobuf decoding
+////////////////////////////////////////////////////////////////////////////////
+
+/// Decodes a `PbNode`, requiring that all links precede the (optional) data.
+fn decode_node(mut bytes: &[u8]) -> Result<PbNode, Error> {
+    let mut links = Vec::new();
+    let mut data = None;
+
+    while !bytes.is_empty() {
+        match read_key(&mut bytes)? {
+            (NODE_LINKS, WIRE_TYPE_LEN) if data.is_none() => {
+                links.push(decode_link(read_len(&mut bytes)?)?);
+            }
+            (NODE_DATA, WIRE_TYPE_LEN) if data.is_none() => {
+                data.replace(Bytes::from(read_len(&mut bytes)?.to_vec()));
+            }
+            (NODE_LINKS, _) | (NODE_DATA, _) if data.is_some() => {
+                return Err(invalid("PBNode `Links` must precede a single `Data`"))
+            }
+            (field, wire_type) => {
+                return Err(invalid(format!(
+                    "unexpected PBNode field {} (wire type {})",
+                    field, wire_type
+                )))
+            }
+        }
+    }
+
+    Ok(PbNode { links, data })
+}
+
+/// Decodes a `PbLink`, requiring that its fields appear in order, at most once.
+fn decode_link(mut bytes: &[u8]) -> Result<PbLink, Error> {
+    let mut hash = None;
+    let mut name = None;
+    let mut tsize = None;
+    let mut last_field = 0;
+
+    while !bytes.is_empty() {
+        let (field, wire_type) = read_key(&mut bytes)?;
+        if field <= last_field {
+            return Err(invalid(
+                "PBLink fields must appear in order `Hash`, `Name`, `Tsize`, at most once",
+            ));
+        }
+        last_field = field;
+
+        match (field, wire_type) {
+            (LINK_HASH, WIRE_TYPE_LEN) => {
+                let cid = Cid::try_from(read_len(&mut bytes)?)?;
+                hash.replace(Link::from(cid));
+            }
+            (LINK_NAME, WIRE_TYPE_LEN) => {
+                let name_bytes = read_len(&mut bytes)?.to_vec();
+                let name_str = String::from_utf8(name_bytes)
+                    .map_err(|_| invalid("PBLink `Name` must be a UTF-8 string"))?;
+                name.replace(name_str);
+            }
+            (LINK_TSIZE, WIRE_TYPE_VARINT) => {
+                tsize.replace(read_varint(&mut bytes)?);
+            }
+            (field, wire_type) => {
+                return Err(invalid(format!(
+                    "unexpected PBLink field {} (wire type {})",
+                    field, wire_type
+                )))
+            }
+        }
+    }
+
+    Ok(PbLink {
+        hash: hash.ok_or_else(|| invalid("PBLink is missing its `Hash`"))?,
+        name,
+        tsize,
+    })
+}
+
+fn invalid(msg: impl std::fmt::Display) -> Error {
+    Error::Decoder(anyhow!("invalid DagPb: {}", msg))
+}
+
+/// Reads a field number and wire type.
+fn read_key(bytes: &mut &[u8]) -> Result<(u64, u64), Error> {
+    let key = read_varint(bytes)?;
+    Ok((key >> 3, key & 0x07))
+}
+
+/// Reads a length-delimited byte sequence.
+fn read_len<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let len = read_varint(bytes)?;
+    match usize::try_from(len) {
+        Ok(len) if len <= bytes.len() => {
+            let (head, tail) = bytes.split_at(len);
+            *bytes = tail;
+            Ok(head)
+        }
+        _ => Err(invalid("unexpected end of input")),
+    }
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, Error> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| invalid("unexpected end of input"))?;
+        *bytes = rest;
+
+        n |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(invalid("varint overflow"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{_codecs::test_utils::*, prelude::*};
+    use std::str::FromStr;
+
+    const CID: &str = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+
+    fn roundtrip<'de>(cases: &[(PbNode, &'de [u8])]) {
+        roundtrip_bytes_codec::<DagPb, PbNode>(cases)
+    }
+
+    fn link(name: Option<&str>, tsize: Option<u64>) -> PbLink {
+        PbLink {
+            hash: Link::from(Cid::from_str(CID).unwrap()),
+            name: name.map(String::from),
+            tsize,
+        }
+    }
+
+    #[test]
+    fn test_node() {
+        let cid_bytes = Cid::from_str(CID).unwrap().to_bytes();
+
+        let empty = PbNode {
+            links: vec![],
+            data: None,
+        };
+        roundtrip(&[(empty, &[][..])]);
+
+        let data = PbNode {
+            links: vec![],
+            data: Some(Bytes::from(vec![0x01, 0x02, 0x03])),
+        };
+        roundtrip(&[(data, &[0x0a, 0x03, 0x01, 0x02, 0x03][..])]);
+
+        // links precede data, and each link's fields are ordered
+        let node = PbNode {
+            links: vec![link(Some("a"), Some(5))],
+            data: Some(Bytes::from(vec![0x01])),
+        };
+        let mut bytes = vec![0x12, 0x29, 0x0a, 0x22];
+        bytes.extend_from_slice(&cid_bytes);
+        bytes.extend_from_slice(&[0x12, 0x01, b'a', 0x18, 0x05, 0x0a, 0x01, 0x01]);
+        roundtrip(&[(node, bytes.as_slice())]);
+    }
+
+    #[test]
+    fn test_strict() {
+        let cid_bytes = Cid::from_str(CID).unwrap().to_bytes();
+        let mut link_bytes = vec![0x0a, 0x22];
+        link_bytes.extend_from_slice(&cid_bytes);
+
+        // data before links
+        let mut bytes = vec![0x0a, 0x01, 0x01, 0x12, 0x24];
+        bytes.extend_from_slice(&link_bytes);
+        assert!(DagPb::decode::<PbNode>(&bytes).is_err());
+
+        // duplicate data
+        let bytes = [0x0a, 0x01, 0x01, 0x0a, 0x01, 0x01];
+        assert!(DagPb::decode::<PbNode>(&bytes).is_err());
+
+        // unknown field
+        let bytes = [0x1a, 0x01, 0x01];
+        assert!(DagPb::decode::<PbNode>(&bytes).is_err());
+
+        // link fields out of order
+        let mut bytes = vec![0x12, 0x27, 0x12, 0x01, b'a'];
+        bytes.extend_from_slice(&link_bytes);
+        assert!(DagPb::decode::<PbNode>(&bytes).is_err());
+
+        // link without a hash
+        let bytes = [0x12, 0x02, 0x18, 0x05];
+        assert!(DagPb::decode::<PbNode>(&bytes).is_err());
+
+        // unsorted links
+        let node = PbNode {
+            links: vec![link(Some("b"), None), link(Some("a"), None)],
+            data: None,
+        };
+        assert!(DagPb::write(&node, Vec::new()).is_err());
+
+        // non-`PbNode` types
+        assert!(DagPb::write(&Null, Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+//! Helpers for CIDs with [identity](https://github.com/multiformats/multihash)
+//! multihashes, i.e. CIDs that embed their (small) content directly.
+
+use crate::dev::*;
+use cid::Error as CidError;
+
+/// The multihash code of the identity "hash" function.
+pub const IDENTITY: u64 = 0x00;
+
+/// Creates a CIDv1 of the given codec, embedding the given bytes within an
+/// identity multihash.
+///
+/// Fails if the bytes don't fit within the multihash size `S`.
+pub fn identity_cid<S: MultihashSize>(codec: u64, bytes: &[u8]) -> Result<CidGeneric<S>, Error> {
+    let mh = multihash::MultihashGeneric::<S>::wrap(IDENTITY, bytes).map_err(CidError::from)?;
+    Ok(CidGeneric::new_v1(codec, mh))
+}
+
+/// Encodes a dag with the codec `C`, embedding the encoded bytes within an
+/// identity-multihash CIDv1.
+pub fn encode_identity_cid<C, T, S>(dag: &T) -> Result<CidGeneric<S>, Error>
+where
+    C: Codec,
+    T: Representation + Serialize,
+    S: MultihashSize,
+{
+    let mut bytes = Vec::new();
+    C::write(dag, &mut bytes)?;
+    identity_cid(C::CODE, &bytes)
+}
+
+/// Returns the bytes embedded within a CID, if it has an identity multihash.
+pub fn identity_bytes<S: MultihashSize>(cid: &CidGeneric<S>) -> Option<&[u8]> {
+    match cid.hash().code() {
+        IDENTITY => Some(cid.hash().digest()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_cid() {
+        let bytes = [0x01, 0x02, 0x03];
+        let cid: Cid = identity_cid(0x55, &bytes).unwrap();
+
+        assert_eq!(cid.version(), cid::Version::V1);
+        assert_eq!(cid.codec(), 0x55);
+        assert_eq!(identity_bytes(&cid), Some(&bytes[..]));
+
+        // bytes larger than the multihash size cannot be embedded
+        assert!(identity_cid::<typenum::U32>(0x55, &[0; 33]).is_err());
+
+        // non-identity CIDs have no embedded bytes
+        let cid = Cid::new_v1(0x55, DefaultMultihash::Sha2_256.digest(&bytes));
+        assert_eq!(identity_bytes(&cid), None);
+    }
+
+    #[cfg(feature = "dag-cbor")]
+    #[test]
+    fn test_encode_identity_cid() {
+        let cid: Cid = encode_identity_cid::<DagCbor, _, _>(&true).unwrap();
+        assert_eq!(cid.codec(), DagCbor::CODE);
+        assert_eq!(identity_bytes(&cid), Some(&[0xf5][..]));
+    }
+}
@@ -4,13 +4,22 @@
 pub mod dag_cbor;
 #[cfg(feature = "dag-json")]
 pub mod dag_json;
+#[cfg(feature = "dag-pb")]
+pub mod dag_pb;
+pub mod identity;
+pub mod multicodec;
+#[cfg(feature = "raw")]
+pub mod raw;
 
 use crate::dev::*;
+use async_trait::async_trait;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use serde::{de, ser};
 use std::{convert::TryFrom, error::Error as StdError};
 
 /// An IPLD [Codec](https://github.com/ipld/specs/blob/master/block-layer/codecs/README.md).
 /// TODO const generic over CODE?
+#[async_trait]
 pub trait Codec: Into<u64> + TryFrom<u64> + Copy {
     /// The multicodec code that identifies this IPLD Codec.
     const CODE: u64;
@@ -26,11 +35,63 @@ pub trait Codec: Into<u64> + TryFrom<u64> + Copy {
     where
         T: Representation + Deserialize<'de>;
 
+    /// Given some bytes, deserialize an owned dag.
+    ///
+    /// The default implementation delegates to `decode`, but codecs that parse
+    /// in-place (i.e. `SimdDagJson`) instead parse a copy of the bytes.
+    fn decode_owned<T>(bytes: &[u8]) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+    {
+        Self::decode(bytes)
+    }
+
     /// Given a `Read`, deserialize a dag.
     fn read<T, R>(reader: R) -> Result<T, Error>
     where
         T: Representation + DeserializeOwned,
         R: Read;
+
+    /// Given a dag and an `AsyncWrite`, encode it to the writer.
+    ///
+    /// This is a buffered convenience wrapper around `write`, not a streaming
+    /// encoder: the default implementation encodes the entire dag to an
+    /// in-memory buffer, then asynchronously writes and flushes the buffer.
+    async fn write_async<T, W>(dag: &T, mut writer: W) -> Result<(), Error>
+    where
+        T: Representation + Serialize + Sync,
+        W: AsyncWrite + Unpin + Send,
+    {
+        let mut bytes = Vec::new();
+        Self::write(dag, &mut bytes)?;
+        writer
+            .write_all(&bytes)
+            .await
+            .map_err(|e| Error::Encoder(anyhow::Error::new(e)))?;
+        writer
+            .flush()
+            .await
+            .map_err(|e| Error::Encoder(anyhow::Error::new(e)))
+    }
+
+    /// Given an `AsyncRead`, deserialize a dag.
+    ///
+    /// The default implementation asynchronously reads the entire block into an
+    /// in-memory buffer, then decodes the buffer with `decode_owned`. `DagCbor`
+    /// and `DagJson` instead read a single dag's bytes as they arrive, without
+    /// reading past its end.
+    async fn read_async<T, R>(mut reader: R) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+        R: AsyncRead + Unpin + Send,
+    {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        Self::decode_owned(&bytes)
+    }
 }
 
 // ///
@@ -176,12 +237,13 @@ mod specialization {
 
 pub(crate) mod test_utils {
     use crate::dev::*;
+    use futures::{executor::block_on, io::Cursor};
     use std::{fmt::Debug, io::Read, string::ToString};
 
     pub fn roundtrip_bytes_codec<'de, C, T>(cases: &[(T, &'de [u8])])
     where
         C: Codec,
-        T: PartialEq + Debug + Representation + Serialize + DeserializeOwned,
+        T: PartialEq + Debug + Representation + Serialize + DeserializeOwned + Sync,
     {
         for (ref dag, expected) in cases {
             // writing
@@ -193,6 +255,16 @@ pub(crate) mod test_utils {
             ));
             assert_eq!(expected, &bytes.as_slice(), "Writing failure");
 
+            // async writing
+            let mut bytes = Vec::new();
+            block_on(C::write_async(dag, &mut bytes)).expect(&format!(
+                "Failed to asynchronously encode `{}` {:?} into {:?}",
+                dag.name(),
+                dag,
+                expected,
+            ));
+            assert_eq!(expected, &bytes.as_slice(), "Async writing failure");
+
             // decoding
             let v = decode_from_bytes::<'de, C, T>(expected).expect(&format!(
                 "Failed to decode `{}` from {:?}",
@@ -201,6 +273,14 @@ pub(crate) mod test_utils {
             ));
             assert_eq!(*dag, v, "Decoding failure");
 
+            // owned decoding
+            let v = C::decode_owned::<T>(expected).expect(&format!(
+                "Failed to decode owned `{}` from {:?}",
+                dag.name(),
+                expected,
+            ));
+            assert_eq!(*dag, v, "Owned decoding failure");
+
             // reading
             let v = C::read(*expected).expect(&format!(
                 "Failed to read `{}` from {:?}",
@@ -208,13 +288,21 @@ pub(crate) mod test_utils {
                 expected,
             ));
             assert_eq!(*dag, v, "Reading failure");
+
+            // async reading
+            let v = block_on(C::read_async(Cursor::new(*expected))).expect(&format!(
+                "Failed to asynchronously read `{}` from {:?}",
+                dag.name(),
+                expected,
+            ));
+            assert_eq!(*dag, v, "Async reading failure");
         }
     }
 
     pub fn roundtrip_str_codec<'de, C, T>(cases: &[(T, &'de str)])
     where
         C: Codec,
-        T: PartialEq + Debug + Representation + Serialize + DeserializeOwned,
+        T: PartialEq + Debug + Representation + Serialize + DeserializeOwned + Sync,
     {
         for (ref dag, expected) in cases {
             // writing
@@ -226,6 +314,17 @@ pub(crate) mod test_utils {
             ));
             assert_eq!(expected, &string.as_str(), "Writing failure");
 
+            // async writing
+            let mut bytes = Vec::new();
+            block_on(C::write_async(dag, &mut bytes)).expect(&format!(
+                "Failed to asynchronously encode `{}` {:?} into {}",
+                dag.name(),
+                dag,
+                expected,
+            ));
+            let string = String::from_utf8(bytes).unwrap();
+            assert_eq!(expected, &string.as_str(), "Async writing failure");
+
             // decoding
             let v = decode_from_str::<'de, C, T>(expected).expect(&format!(
                 "Failed to decode `{}` from {}",
@@ -234,6 +333,14 @@ pub(crate) mod test_utils {
             ));
             assert_eq!(*dag, v, "Decoding failure");
 
+            // owned decoding
+            let v = C::decode_owned::<T>(expected.as_bytes()).expect(&format!(
+                "Failed to decode owned `{}` from {}",
+                dag.name(),
+                expected,
+            ));
+            assert_eq!(*dag, v, "Owned decoding failure");
+
             // reading
             let v = C::read(expected.as_bytes()).expect(&format!(
                 "Failed to read `{}` from {}",
@@ -241,6 +348,14 @@ pub(crate) mod test_utils {
                 expected,
             ));
             assert_eq!(*dag, v, "Reading failure");
+
+            // async reading
+            let v = block_on(C::read_async(Cursor::new(expected.as_bytes()))).expect(&format!(
+                "Failed to asynchronously read `{}` from {}",
+                dag.name(),
+                expected,
+            ));
+            assert_eq!(*dag, v, "Async reading failure");
         }
     }
 
@@ -279,3 +394,149 @@ pub(crate) mod test_utils {
         C::decode(s.as_bytes())
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test_schemas {
+    //! Schemas shared by the codec tests.
+
+    use crate::dev::*;
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Names [String];
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type NullableInts [nullable Int];
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Counts {String:Int};
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type PairCounts {String:Int} representation listpairs;
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type QueryCounts {String:Int} representation stringpairs {
+            innerDelim "="
+            entryDelim "&"
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Point struct {
+            pub x Int,
+            pub y Int,
+            pub label optional String,
+        } representation tuple {
+            fieldOrder ["y", "x", "label"]
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Range struct {
+            pub start Int,
+            pub end optional nullable Int,
+            pub step optional Int,
+        } representation tuple;
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Version struct {
+            pub major Int,
+            pub minor Int,
+        } representation stringjoin {
+            join "."
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Query struct {
+            pub name String (rename "n"),
+            pub limit Int (implicit "10"),
+            pub verbose optional bool,
+        } representation stringpairs {
+            innerDelim "="
+            entryDelim ","
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Zoom struct {
+            pub level Float (implicit "1"),
+        } representation stringpairs {
+            innerDelim "="
+            entryDelim ","
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Entry struct {
+            pub name String (rename "Name"),
+            pub size optional Int,
+            pub count Int (implicit "1"),
+        } representation listpairs;
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Circle struct {
+            pub radius Int,
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Square struct {
+            pub side Int,
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type Shape union {
+            | Circle "circle"
+            | Square "square"
+        } representation envelope {
+            discriminantKey "tag"
+            contentKey "content"
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        pub type InlineShape union {
+            | Circle "circle"
+            | Square "square"
+        } representation inline {
+            discriminantKey "tag"
+        };
+    }
+}
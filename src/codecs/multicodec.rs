@@ -0,0 +1,213 @@
+//! A runtime registry of `Codec`s, for encoding and decoding dags whose codec
+//! is only known at runtime (e.g. from a CID).
+//!
+//! Codecs enabled by cargo features are always available; custom codecs can
+//! be registered (or override an enabled codec) with `register`.
+
+use crate::dev::*;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::{PoisonError, RwLock},
+};
+
+/// Registered encode and decode functions, keyed by multicodec code.
+static REGISTRY: Lazy<RwLock<HashMap<u64, CodecFns>>> = Lazy::new(Default::default);
+
+/// A `Codec`'s `write` and `decode` functions, type-erased by encoding from
+/// and decoding into `Value`s.
+///
+/// Dags are therefore converted to and from `Value`s when using a registered
+/// codec, so any links within them must fit within the `DefaultMultihashSize`.
+#[derive(Clone, Copy)]
+struct CodecFns {
+    write: fn(&Value, &mut dyn Write) -> Result<(), Error>,
+    decode: fn(&[u8]) -> Result<Value, Error>,
+}
+
+fn poisoned<T>(_: PoisonError<T>) -> Error {
+    Error::Other("multicodec registry poisoned".into())
+}
+
+/// Registers a custom `Codec` `C` for encoding and decoding dags of any type,
+/// replacing any codec previously registered (or enabled) for `C::CODE`.
+pub fn register<C: Codec>() -> Result<(), Error> {
+    let fns = CodecFns {
+        write: |dag, writer| C::write(dag, writer),
+        decode: |bytes| C::decode_owned(bytes),
+    };
+
+    REGISTRY.write().map_err(poisoned)?.insert(C::CODE, fns);
+    Ok(())
+}
+
+/// Looks up the `CodecFns` registered for a code.
+fn registered(code: u64) -> Result<Option<CodecFns>, Error> {
+    Ok(REGISTRY.read().map_err(poisoned)?.get(&code).copied())
+}
+
+/// Given a multicodec code, a dag and a `Write`, encode the dag to the writer.
+pub fn write_any<T, W>(code: u64, dag: &T, mut writer: W) -> Result<(), Error>
+where
+    T: Representation + Serialize,
+    W: Write,
+{
+    if let Some(fns) = registered(code)? {
+        let value = to_value(dag)?;
+        return (fns.write)(&value, &mut writer);
+    }
+
+    match code {
+        #[cfg(feature = "dag-cbor")]
+        DagCbor::CODE => DagCbor::write(dag, writer),
+        #[cfg(feature = "dag-json")]
+        DagJson::CODE => DagJson::write(dag, writer),
+        #[cfg(feature = "dag-pb")]
+        DagPb::CODE => DagPb::write(dag, writer),
+        #[cfg(feature = "raw")]
+        Raw::CODE => Raw::write(dag, writer),
+        _ => Err(Error::UnknownCodec(code)),
+    }
+}
+
+/// Given a multicodec code and a dag, encode the dag to bytes.
+pub fn encode_any<T>(code: u64, dag: &T) -> Result<Vec<u8>, Error>
+where
+    T: Representation + Serialize,
+{
+    let mut bytes = Vec::new();
+    write_any(code, dag, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Given a multicodec code and some bytes, deserialize a dag.
+pub fn decode_any<T>(code: u64, bytes: &[u8]) -> Result<T, Error>
+where
+    T: Representation + DeserializeOwned,
+{
+    if let Some(fns) = registered(code)? {
+        return from_value((fns.decode)(bytes)?);
+    }
+
+    match code {
+        #[cfg(feature = "dag-cbor")]
+        DagCbor::CODE => DagCbor::decode_owned(bytes),
+        #[cfg(feature = "dag-json")]
+        DagJson::CODE => DagJson::decode_owned(bytes),
+        #[cfg(feature = "dag-pb")]
+        DagPb::CODE => DagPb::decode_owned(bytes),
+        #[cfg(feature = "raw")]
+        Raw::CODE => Raw::decode_owned(bytes),
+        _ => Err(Error::UnknownCodec(code)),
+    }
+}
+
+/// Given a multicodec code and a `Read`, deserialize a dag.
+pub fn read_any<T, R>(code: u64, mut reader: R) -> Result<T, Error>
+where
+    T: Representation + DeserializeOwned,
+    R: Read,
+{
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+    decode_any(code, &bytes)
+}
+
+#[cfg(all(test, feature = "dag-cbor", feature = "dag-json"))]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    /// A custom codec, that aliases `DagJson` under another code.
+    #[derive(Clone, Copy, Debug)]
+    struct JsonAlias;
+
+    impl Into<u64> for JsonAlias {
+        fn into(self) -> u64 {
+            Self::CODE
+        }
+    }
+
+    impl TryFrom<u64> for JsonAlias {
+        type Error = Error;
+        fn try_from(code: u64) -> Result<Self, Self::Error> {
+            match code {
+                Self::CODE => Ok(Self),
+                _ => Err(Error::UnknownCodec(code)),
+            }
+        }
+    }
+
+    impl Codec for JsonAlias {
+        const CODE: u64 = 0x30_0000;
+
+        fn write<T, W>(dag: &T, writer: W) -> Result<(), Error>
+        where
+            T: Representation + Serialize,
+            W: Write,
+        {
+            DagJson::write(dag, writer)
+        }
+
+        fn decode<'de, T>(bytes: &'de [u8]) -> Result<T, Error>
+        where
+            T: Representation + Deserialize<'de>,
+        {
+            DagJson::decode(bytes)
+        }
+
+        fn read<T, R>(reader: R) -> Result<T, Error>
+        where
+            T: Representation + DeserializeOwned,
+            R: Read,
+        {
+            DagJson::read(reader)
+        }
+    }
+
+    #[test]
+    fn test_builtin() {
+        let bytes = encode_any(DagCbor::CODE, &true).unwrap();
+        assert_eq!(bytes, vec![0xf5]);
+        assert!(decode_any::<bool>(DagCbor::CODE, &bytes).unwrap());
+
+        let bytes = encode_any(DagJson::CODE, &true).unwrap();
+        assert_eq!(bytes, b"true".to_vec());
+        assert!(decode_any::<bool>(DagJson::CODE, &bytes).unwrap());
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn test_register_simd() {
+        register::<SimdDagJson>().unwrap();
+        let bytes = encode_any(DagJson::CODE, &true).unwrap();
+        assert_eq!(bytes, b"true".to_vec());
+        assert!(decode_any::<bool>(DagJson::CODE, &bytes).unwrap());
+    }
+
+    #[test]
+    fn test_unknown() {
+        let err = encode_any(0x30_0001, &true).unwrap_err();
+        assert!(matches!(err, Error::UnknownCodec(0x30_0001)));
+        let err = decode_any::<bool>(0x30_0001, b"true").unwrap_err();
+        assert!(matches!(err, Error::UnknownCodec(0x30_0001)));
+    }
+
+    #[test]
+    fn test_register() {
+        let dag = String::from("hello");
+        assert!(encode_any(JsonAlias::CODE, &dag).is_err());
+
+        register::<JsonAlias>().unwrap();
+        let bytes = encode_any(JsonAlias::CODE, &dag).unwrap();
+        assert_eq!(bytes, b"\"hello\"".to_vec());
+        assert_eq!(decode_any::<String>(JsonAlias::CODE, &bytes).unwrap(), dag);
+
+        // registered codecs are available for any dag type
+        let bytes = encode_any(JsonAlias::CODE, &true).unwrap();
+        assert_eq!(bytes, b"true".to_vec());
+        assert!(decode_any::<bool>(JsonAlias::CODE, &bytes).unwrap());
+    }
+}
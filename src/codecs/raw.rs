@@ -0,0 +1,116 @@
+//! IPLD Raw codec.
+
+use crate::dev::*;
+use anyhow::anyhow;
+use std::convert::TryFrom;
+
+/// The [Raw](https://github.com/multiformats/multicodec/blob/master/table.csv)
+/// codec, whose blocks are represented as `Bytes`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Raw;
+
+impl Into<u64> for Raw {
+    fn into(self) -> u64 {
+        Self::CODE
+    }
+}
+
+impl TryFrom<u64> for Raw {
+    type Error = Error;
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        match code {
+            Self::CODE => Ok(Self),
+            _ => Err(Error::UnknownCodec(code)),
+        }
+    }
+}
+
+impl Codec for Raw {
+    const CODE: u64 = 0x55;
+
+    fn write<T, W>(dag: &T, mut writer: W) -> Result<(), Error>
+    where
+        T: Representation + Serialize,
+        W: Write,
+    {
+        writer
+            .write_all(dag.as_raw_bytes()?)
+            .map_err(|e| Error::Encoder(anyhow::Error::new(e)))
+    }
+
+    fn decode<'de, T>(bytes: &'de [u8]) -> Result<T, Error>
+    where
+        T: Representation + Deserialize<'de>,
+    {
+        T::from_raw_bytes(bytes.to_vec())
+    }
+
+    fn read<T, R>(mut reader: R) -> Result<T, Error>
+    where
+        T: Representation + DeserializeOwned,
+        R: Read,
+    {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::Decoder(anyhow::Error::new(e)))?;
+        T::from_raw_bytes(bytes)
+    }
+}
+
+/// Converts dags to and from raw bytes, which is only supported by `Bytes`.
+trait RawRepresentation: Sized {
+    fn as_raw_bytes(&self) -> Result<&[u8], Error>;
+    fn from_raw_bytes(bytes: Vec<u8>) -> Result<Self, Error>;
+}
+
+/// Default (specialized) implementation, which rejects all non-`Bytes` types.
+impl<T: Representation> RawRepresentation for T {
+    #[inline]
+    default fn as_raw_bytes(&self) -> Result<&[u8], Error> {
+        Err(Error::Encoder(anyhow!(
+            "Raw can only encode `Bytes`, not `{}`",
+            T::NAME
+        )))
+    }
+
+    #[inline]
+    default fn from_raw_bytes(_: Vec<u8>) -> Result<Self, Error> {
+        Err(Error::Decoder(anyhow!(
+            "Raw can only decode `Bytes`, not `{}`",
+            T::NAME
+        )))
+    }
+}
+
+impl RawRepresentation for Bytes {
+    #[inline]
+    fn as_raw_bytes(&self) -> Result<&[u8], Error> {
+        Ok(AsRef::<[u8]>::as_ref(self))
+    }
+
+    #[inline]
+    fn from_raw_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        Ok(Bytes::from(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{_codecs::test_utils::*, prelude::*};
+
+    #[test]
+    fn test_bytes() {
+        let tests = &[
+            (Bytes::from(vec![]), &[][..]),
+            (Bytes::from(vec![0x01, 0x02, 0x03]), &[0x01, 0x02, 0x03][..]),
+        ];
+        roundtrip_bytes_codec::<Raw, Bytes>(tests);
+    }
+
+    #[test]
+    fn test_other() {
+        assert!(Raw::write(&Null, Vec::new()).is_err());
+        assert!(Raw::decode::<String>(b"hello").is_err());
+    }
+}
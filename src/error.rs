@@ -2,7 +2,7 @@ use crate::dev::*;
 use cid::Error as CidError;
 use serde::{de, ser};
 use std::{
-    convert::Infallible, error::Error as StdError, num::TryFromIntError, string::FromUtf8Error,
+    convert::Infallible, error::Error as StdError, fmt, num::TryFromIntError, string::FromUtf8Error,
 };
 use thiserror::Error;
 
@@ -31,6 +31,43 @@ pub enum Error {
     #[error("Unknown codec error: {0}")]
     UnknownCodec(u64),
 
+    #[error("Invalid link: unexpected CBOR tag {0}")]
+    UnexpectedCborTag(u64),
+
+    #[error("Invalid link: missing multibase identity prefix `0x00`")]
+    MissingLinkPrefix,
+
+    #[error("Invalid link: invalid CID string `{cid_str}`: {source}")]
+    InvalidLinkString { cid_str: String, source: CidError },
+
+    #[error("Invalid link target: link to {type_name} cannot resolve CID {cid}: {reason}")]
+    InvalidLinkTarget {
+        type_name: &'static str,
+        cid: String,
+        reason: String,
+    },
+
+    #[error("Block store error: {0}")]
+    BlockStore(anyhow::Error),
+
+    #[error("Block not found: {0}")]
+    BlockNotFound(String),
+
+    #[error("Invalid block: bytes do not match CID {0}")]
+    InvalidBlock(String),
+
+    #[error("Invalid CAR: {0}")]
+    InvalidCar(String),
+
+    #[error("I/O error: {0}")]
+    Io(std::io::Error),
+
+    #[error("Non-canonical encoding: {0}")]
+    NonCanonical(String),
+
+    #[error("Unexpected IPLD kind: expected {expected:?}, found {found:?}")]
+    UnexpectedKind { expected: Kind, found: Kind },
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -46,6 +83,30 @@ impl Error {
             selector_name: selector.name(),
         }
     }
+
+    pub fn invalid_link_target<T, S>(cid: &CidGeneric<S>, reason: impl ToString) -> Self
+    where
+        T: Representation,
+        S: MultihashSize,
+    {
+        Self::InvalidLinkTarget {
+            type_name: <T as Representation>::NAME,
+            cid: cid.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Encoder(anyhow::anyhow!("{}", msg))
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Decoder(anyhow::anyhow!("{}", msg))
+    }
 }
 
 impl From<CidError> for Error {
@@ -1,36 +1,12 @@
 //! An IPLD type that can borrow most of it's contents from an underlying type.
 //!
-//! TODO: implement mutation methods, getters, etc - you know, fun stuff
-
-// TODO: edit this
-/// IPLD introduces two fundamental concepts, Data Types and Schemas/Representations.
-/// In short, Data Types are a small set of common types (lists, maps, links, etc)
-/// necessary for generally modelling Linked Data and can be serialized/deserialized
-/// by an IPLD CodecExt. However, more advanced types might benefit from having schemas,
-/// alternate serialization/deserialization behaviour, or runtime dependencies to
-/// aid in verification when encoding/decoding the type to/from raw blocks.
-///
-/// This type fulfills the role of a low-allocation mapping between IPLD Schemas
-/// /Representations and underlying Codecs by actually providing two mappings:
-///     - one from IPLD Data type <-> IPLD CodecExt (via Serde data model)
-///     - one from Rust types, schemas & representations <-> IPLD (via `TryFrom`)
-/// In the first case, Serde only copies/borrows from the type on serialization,
-/// and whenever possible provides borrowed types on deserialization. Likewise,
-/// `TryFrom<Ipld>` and `TryInto<Ipld>` are implemented to borrow many of their
-/// fields or lazily iterate over them.
-//
-
-///
-#[cfg(feature = "graphql")]
-#[path = "./juniper.rs"]
-mod _juniper;
+//! Decoding a borrowed `Ipld` from a block's bytes (e.g. with `Codec::decode`)
+//! borrows strings, bytes and map keys from the bytes whenever the `Codec`
+//! allows it, and `TryFrom<Ipld>` converts it into native types.
 
 use crate::dev::*;
-use serde::{
-    de::{self, Visitor},
-    serde_if_integer128, Deserialize, Deserializer, Serialize, Serializer,
-};
-use std::{collections::BTreeMap, fmt};
+use serde::{de, serde_if_integer128};
+use std::{borrow::Cow, collections::BTreeMap, convert::TryFrom, fmt};
 
 /// An Ipld type that borrows most of its contents from an underlying native
 /// type or `Deserializer`.
@@ -74,12 +50,42 @@ pub enum Ipld<'a> {
     BytesBuf(Vec<u8>),
     /// Represents a list.
     List(Vec<Ipld<'a>>),
-    /// Represents a map.
-    Map(BTreeMap<&'a str, Ipld<'a>>),
+    /// Represents a map, whose keys are borrowed whenever possible.
+    Map(BTreeMap<Cow<'a, str>, Ipld<'a>>),
     /// Represents a link to an Ipld node.
     Link(Cid),
 }
 
+impl<'a> Ipld<'a> {
+    /// The IPLD data model kind of the `Ipld`.
+    pub fn kind(&self) -> Kind {
+        match self {
+            Ipld::Null => Kind::Null,
+            Ipld::Bool(_) => Kind::Boolean,
+            Ipld::Int8(_)
+            | Ipld::Int16(_)
+            | Ipld::Int32(_)
+            | Ipld::Int64(_)
+            | Ipld::Int128(_)
+            | Ipld::Uint8(_)
+            | Ipld::Uint16(_)
+            | Ipld::Uint32(_)
+            | Ipld::Uint64(_)
+            | Ipld::Uint128(_) => Kind::Integer,
+            Ipld::Float32(_) | Ipld::Float64(_) => Kind::Float,
+            Ipld::Str(_) | Ipld::String(_) => Kind::String,
+            Ipld::Bytes(_) | Ipld::BytesBuf(_) => Kind::Bytes,
+            Ipld::List(_) => Kind::List,
+            Ipld::Map(_) => Kind::Map,
+            Ipld::Link(_) => Kind::Link,
+        }
+    }
+}
+
+impl<'a> Representation for Ipld<'a> {
+    const NAME: &'static str = "Ipld";
+}
+
 /// `Serialize` implementation that delegates to an `Encoder` for bytes and links,
 /// otherwise directly calls methods on the `Serializer`.
 impl<'a> Serialize for Ipld<'a> {
@@ -122,7 +128,7 @@ impl<'de> Deserialize<'de> for Ipld<'de> {
     where
         D: Deserializer<'de>,
     {
-        <D as Decoder>::deserialize_any(deserializer, IpldVisitor)
+        <D as Decoder<'de>>::deserialize_any(deserializer, IpldVisitor)
     }
 }
 
@@ -205,11 +211,11 @@ impl<'de> Visitor<'de> for IpldVisitor {
     visit_primitive!(&[u8] : visit_bytes BytesBuf into);
 
     #[inline]
-    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: de::Deserializer<'de>,
     {
-        deserializer.deserialize_link(self)
+        <D as Decoder<'de>>::deserialize_any(deserializer, self)
     }
 
     #[inline]
@@ -235,10 +241,13 @@ impl<'de> Visitor<'de> for IpldVisitor {
     where
         A: de::MapAccess<'de>,
     {
-        let mut btree: BTreeMap<&'de str, Ipld<'de>> = BTreeMap::new();
+        let mut btree: BTreeMap<Cow<'de, str>, Ipld<'de>> = BTreeMap::new();
 
-        while let Some((key, value)) = map.next_entry()? {
-            btree.insert(key, value);
+        while let Some(KeyVisitor(key)) = map.next_key()? {
+            if btree.contains_key(&key) {
+                return Err(de::Error::custom(format!("duplicate map key `{}`", key)));
+            }
+            btree.insert(key, map.next_value()?);
         }
 
         Ok(Ipld::Map(btree))
@@ -247,10 +256,123 @@ impl<'de> Visitor<'de> for IpldVisitor {
 
 impl<'de> IpldVisitorExt<'de> for IpldVisitor {
     #[inline]
-    fn visit_link<E>(self, cid: Cid) -> Result<<Self as Visitor<'de>>::Value, E>
+    fn visit_link<E>(self, cid_bytes: Box<[u8]>) -> Result<<Self as Visitor<'de>>::Value, E>
     where
         E: de::Error,
     {
+        let cid = Cid::try_from(cid_bytes.as_ref()).map_err(|err| E::custom(Error::Cid(err)))?;
         Ok(Ipld::Link(cid))
     }
 }
+
+/// Deserializes a map key, borrowing it from the input whenever possible.
+struct KeyVisitor<'de>(Cow<'de, str>);
+
+impl<'de> Deserialize<'de> for KeyVisitor<'de> {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(KeyVisitor(Cow::Borrowed("")))
+    }
+}
+
+impl<'de> Visitor<'de> for KeyVisitor<'de> {
+    type Value = Self;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string map key")
+    }
+
+    #[inline]
+    fn visit_borrowed_str<E>(self, key: &'de str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(KeyVisitor(Cow::Borrowed(key)))
+    }
+
+    #[inline]
+    fn visit_str<E>(self, key: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(KeyVisitor(Cow::Owned(key.into())))
+    }
+
+    #[inline]
+    fn visit_string<E>(self, key: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(KeyVisitor(Cow::Owned(key)))
+    }
+}
+
+#[cfg(all(test, feature = "dag-cbor"))]
+mod tests {
+    use super::*;
+
+    fn borrows_from(s: &[u8], bytes: &[u8]) -> bool {
+        bytes.as_ptr_range().contains(&s.as_ptr())
+    }
+
+    #[test]
+    fn test_borrowed_decode() {
+        let block = Block::<DefaultMultihashSize>::encode::<DagCbor, _>(&true, 0x12).unwrap();
+        let cid = block.cid().clone();
+
+        let mut map = BTreeMap::new();
+        map.insert(Cow::Borrowed("name"), Ipld::Str("hello"));
+        map.insert(Cow::Borrowed("data"), Ipld::Bytes(&[1, 2, 3]));
+        map.insert(Cow::Borrowed("link"), Ipld::from(&cid));
+        map.insert(Cow::Borrowed("size"), Ipld::Uint64(300));
+        let mut bytes = Vec::new();
+        DagCbor::write(&Ipld::Map(map), &mut bytes).unwrap();
+
+        let ipld: Ipld<'_> = DagCbor::decode(&bytes).unwrap();
+        let mut map = match ipld {
+            Ipld::Map(map) => map,
+            ipld => panic!("expected a map, found {:?}", ipld.kind()),
+        };
+        assert!(map
+            .keys()
+            .all(|key| matches!(key, Cow::Borrowed(k) if borrows_from(k.as_bytes(), &bytes))));
+
+        let name = <&str>::try_from(map.remove("name").unwrap()).unwrap();
+        assert_eq!(name, "hello");
+        assert!(borrows_from(name.as_bytes(), &bytes));
+
+        let data = <&[u8]>::try_from(map.remove("data").unwrap()).unwrap();
+        assert_eq!(data, &[1, 2, 3]);
+        assert!(borrows_from(data, &bytes));
+
+        assert_eq!(Cid::try_from(map.remove("link").unwrap()).unwrap(), cid);
+        assert_eq!(u16::try_from(map.remove("size").unwrap()).unwrap(), 300);
+
+        // duplicate keys
+        let bytes = [0xa2, 0x61, 0x61, 0x01, 0x61, 0x61, 0x02];
+        assert!(DagCbor::decode::<Ipld<'_>>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_try_from() {
+        assert_eq!(i8::try_from(Ipld::Uint64(127)).unwrap(), 127);
+        assert!(matches!(u8::try_from(Ipld::Int8(-1)), Err(Error::Other(_))));
+        assert_eq!(f64::try_from(Ipld::Float32(0.5)).unwrap(), 0.5);
+        assert_eq!(
+            String::try_from(Ipld::String("owned".into())).unwrap(),
+            "owned"
+        );
+        assert!(<&str>::try_from(Ipld::String("owned".into())).is_err());
+        assert!(matches!(
+            bool::try_from(Ipld::Null),
+            Err(Error::UnexpectedKind {
+                expected: Kind::Boolean,
+                found: Kind::Null,
+            })
+        ));
+    }
+}
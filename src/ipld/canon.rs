@@ -1,21 +1,12 @@
-//! Canonicalization conversion implementations.
-//!
-//!
-
-use crate::{borrowed::Ipld, CodecExt, Error};
-use cid::Cid;
-use failure::format_err;
-use std::{convert::TryFrom, marker::PhantomData};
-
-/// Shorthand for deriving `From<& _>` for a reference to a type.
-#[doc(hidden)]
-#[macro_export(local_inner_macros)]
+//! Conversions between the borrowed `Ipld` and native Rust types.
+
+use crate::{dev::*, ipld::borrowed::Ipld};
+use std::convert::TryFrom;
+
+/// Shorthand for deriving `From<&_>` for a reference to a `Copy` type.
 macro_rules! borrowed_ipld_from_ref {
     ($type:ty : $member:ident) => {
-        impl<'a> From<&'a $type> for Ipld<'a>
-        where
-            C: CodecExt,
-        {
+        impl<'a> From<&'a $type> for Ipld<'a> {
             #[inline]
             fn from(t: &'a $type) -> Ipld<'a> {
                 Ipld::$member(*t)
@@ -24,72 +15,80 @@ macro_rules! borrowed_ipld_from_ref {
     };
 }
 
+#[inline]
+fn unexpected(expected: Kind, ipld: &Ipld<'_>) -> Error {
+    Error::UnexpectedKind {
+        expected,
+        found: ipld.kind(),
+    }
+}
+
 // null
 
-impl<'a> TryFrom<Ipld<'a>> for ()
-where
-    C: CodecExt,
-{
+impl<'a> TryFrom<Ipld<'a>> for () {
     type Error = Error;
 
     #[inline]
     fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
         match ipld {
-            Ipld::Null(_) => Ok(()),
-            _ => Err(Error::Ipld(format_err!("Not Null"))),
+            Ipld::Null => Ok(()),
+            _ => Err(unexpected(Kind::Null, &ipld)),
         }
     }
 }
 
-impl<'a> From<&'a ()> for Ipld<'a>
-where
-    C: CodecExt,
-{
+impl<'a> From<&'a ()> for Ipld<'a> {
     #[inline]
     fn from(_: &'a ()) -> Self {
-        Ipld::Null(PhantomData)
+        Ipld::Null
     }
 }
 
 // bool
 
 borrowed_ipld_from_ref!(bool: Bool);
-impl<'a> TryFrom<Ipld<'a>> for bool
-where
-    C: CodecExt,
-{
+impl<'a> TryFrom<Ipld<'a>> for bool {
     type Error = Error;
 
     #[inline]
     fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
         match ipld {
             Ipld::Bool(b) => Ok(b),
-            _ => Err(Error::Ipld(format_err!("Not Bool"))),
+            _ => Err(unexpected(Kind::Boolean, &ipld)),
         }
     }
 }
 
 // int
 
-#[doc(hidden)]
-#[macro_export(local_inner_macros)]
-macro_rules! try_from_num {
+/// Converts from any integer `Ipld`, failing if the integer doesn't fit.
+macro_rules! try_from_int {
     ($type:ty : $member:ident) => {
-        impl<'a> TryFrom<Ipld<'a>> for $type
-        where
-            C: CodecExt,
-        {
+        impl<'a> TryFrom<Ipld<'a>> for $type {
             type Error = Error;
 
             #[inline]
             fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
-                match ipld {
-                    Ipld::$member(i) => Ok(i),
-                    _ => Err(Error::Ipld(::failure::format_err!(
-                        "Not {}",
-                        ::std::stringify!($type)
-                    ))),
-                }
+                let res = match ipld {
+                    Ipld::Int8(n) => <$type>::try_from(n).ok(),
+                    Ipld::Int16(n) => <$type>::try_from(n).ok(),
+                    Ipld::Int32(n) => <$type>::try_from(n).ok(),
+                    Ipld::Int64(n) => <$type>::try_from(n).ok(),
+                    Ipld::Int128(n) => <$type>::try_from(n).ok(),
+                    Ipld::Uint8(n) => <$type>::try_from(n).ok(),
+                    Ipld::Uint16(n) => <$type>::try_from(n).ok(),
+                    Ipld::Uint32(n) => <$type>::try_from(n).ok(),
+                    Ipld::Uint64(n) => <$type>::try_from(n).ok(),
+                    Ipld::Uint128(n) => <$type>::try_from(n).ok(),
+                    _ => return Err(unexpected(Kind::Integer, &ipld)),
+                };
+
+                res.ok_or_else(|| {
+                    Error::Other(format!(
+                        "integer out of range for {}",
+                        stringify!($type)
+                    ))
+                })
             }
         }
 
@@ -97,249 +96,149 @@ macro_rules! try_from_num {
     };
 }
 
-try_from_num!(i8: Int8);
-try_from_num!(i16: Int16);
-try_from_num!(i32: Int32);
-try_from_num!(i64: Int64);
-try_from_num!(i128: Int128);
-try_from_num!(u8: Uint8);
-try_from_num!(u16: Uint16);
-try_from_num!(u32: Uint32);
-try_from_num!(u64: Uint64);
-try_from_num!(u128: Uint128);
-try_from_num!(f32: Float32);
-try_from_num!(f64: Float64);
+try_from_int!(i8: Int8);
+try_from_int!(i16: Int16);
+try_from_int!(i32: Int32);
+try_from_int!(i64: Int64);
+try_from_int!(i128: Int128);
+try_from_int!(u8: Uint8);
+try_from_int!(u16: Uint16);
+try_from_int!(u32: Uint32);
+try_from_int!(u64: Uint64);
+try_from_int!(u128: Uint128);
+
+// float
+
+borrowed_ipld_from_ref!(f32: Float32);
+impl<'a> TryFrom<Ipld<'a>> for f32 {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::Float32(f) => Ok(f),
+            _ => Err(unexpected(Kind::Float, &ipld)),
+        }
+    }
+}
+
+borrowed_ipld_from_ref!(f64: Float64);
+impl<'a> TryFrom<Ipld<'a>> for f64 {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
+        match ipld {
+            Ipld::Float32(f) => Ok(f.into()),
+            Ipld::Float64(f) => Ok(f),
+            _ => Err(unexpected(Kind::Float, &ipld)),
+        }
+    }
+}
 
 // string
 
-impl<'a> TryFrom<Ipld<'a>> for &'a str
-where
-    C: CodecExt,
-{
+impl<'a> TryFrom<Ipld<'a>> for &'a str {
     type Error = Error;
 
     #[inline]
     fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
         match ipld {
-            Ipld::String(s) => Ok(s),
-            _ => Err(Error::Ipld(format_err!("Not String"))),
+            Ipld::Str(s) => Ok(s),
+            Ipld::String(_) => Err(Error::Other("cannot borrow an allocated string".into())),
+            _ => Err(unexpected(Kind::String, &ipld)),
         }
     }
 }
 
-impl<'a> TryFrom<Ipld<'a>> for String
-where
-    C: CodecExt,
-{
+impl<'a> TryFrom<Ipld<'a>> for String {
     type Error = Error;
 
     #[inline]
     fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
         match ipld {
-            Ipld::String(s) => Ok(s.into()),
-            _ => Err(Error::Ipld(format_err!("Not String"))),
+            Ipld::Str(s) => Ok(s.into()),
+            Ipld::String(s) => Ok(s),
+            _ => Err(unexpected(Kind::String, &ipld)),
         }
     }
 }
 
-impl<'a> From<&'a String> for Ipld<'a>
-where
-    C: CodecExt,
-{
+impl<'a> From<&'a str> for Ipld<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Ipld::Str(s)
+    }
+}
+
+impl<'a> From<&'a String> for Ipld<'a> {
     #[inline]
     fn from(s: &'a String) -> Self {
-        Ipld::String(&*s)
+        Ipld::Str(s.as_str())
     }
 }
 
 // bytes
 
-impl<'a> TryFrom<Ipld<'a>> for &'a [u8]
-where
-    C: CodecExt,
-{
+impl<'a> TryFrom<Ipld<'a>> for &'a [u8] {
     type Error = Error;
 
     #[inline]
     fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
         match ipld {
             Ipld::Bytes(b) => Ok(b),
-            _ => Err(Error::Ipld(format_err!("Not Bytes"))),
+            Ipld::BytesBuf(_) => Err(Error::Other(
+                "cannot borrow an allocated byte buffer".into(),
+            )),
+            _ => Err(unexpected(Kind::Bytes, &ipld)),
         }
     }
 }
 
-impl<'a> From<&'a Box<[u8]>> for Ipld<'a>
-where
-    C: CodecExt,
-{
-    #[inline]
-    fn from(bytes: &'a Box<[u8]>) -> Self {
-        Ipld::Bytes(&*bytes)
-    }
-}
-
-#[cfg(feature = "bytes_")]
-impl<'a> TryFrom<Ipld<'a>> for bytes::Bytes
-where
-    C: CodecExt,
-{
+impl<'a> TryFrom<Ipld<'a>> for Vec<u8> {
     type Error = Error;
 
     #[inline]
     fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
         match ipld {
-            Ipld::Bytes(b) => Ok(bytes::Bytes::copy_from_slice(b)),
-            _ => Err(Error::Ipld(format_err!("Not Bytes"))),
+            Ipld::Bytes(b) => Ok(b.into()),
+            Ipld::BytesBuf(b) => Ok(b),
+            _ => Err(unexpected(Kind::Bytes, &ipld)),
         }
     }
 }
 
-#[cfg(feature = "bytes_")]
-impl<'a> From<&'a bytes::Bytes> for Ipld<'a>
-where
-    C: CodecExt,
-{
+impl<'a> From<&'a [u8]> for Ipld<'a> {
+    #[inline]
+    fn from(bytes: &'a [u8]) -> Self {
+        Ipld::Bytes(bytes)
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for Ipld<'a> {
     #[inline]
-    fn from(bytes: &'a bytes::Bytes) -> Self {
-        Ipld::Bytes(bytes.as_ref())
+    fn from(bytes: &'a Vec<u8>) -> Self {
+        Ipld::Bytes(bytes.as_slice())
     }
 }
 
 // cid
 
-impl<'a> TryFrom<Ipld<'a>> for Cid
-where
-    C: CodecExt,
-{
+impl<'a> TryFrom<Ipld<'a>> for Cid {
     type Error = Error;
 
     #[inline]
     fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
         match ipld {
             Ipld::Link(cid) => Ok(cid),
-            _ => Err(Error::Ipld(format_err!("Not Link"))),
+            _ => Err(unexpected(Kind::Link, &ipld)),
         }
     }
 }
 
-impl<'a> From<&'a Cid> for Ipld<'a>
-where
-    C: CodecExt,
-{
+impl<'a> From<&'a Cid> for Ipld<'a> {
     #[inline]
     fn from(link: &'a Cid) -> Self {
-        Ipld::Link(link.to_owned())
+        Ipld::Link(link.clone())
     }
 }
-
-/// Derives `TryFrom<Ipld>` for an arbitrary struct.
-/// Used within other macros that produce an IPLD `Representation` for custom types.
-#[macro_export]
-macro_rules! derive_ipld_for_struct {
-    ($name:ident { $($member:ident : $value_type:ty,)* }) => {
-        ::paste::item! {
-            #[doc(hidden)]
-            enum [<$name Field>] {
-                $([<Field $member>],)*
-            }
-
-            impl [<$name Field>] {
-                #[inline]
-                fn field(key: &str) -> Result<Self, Error> {
-                    match key {
-                        $(::std::stringify!($member) => Ok(Self::$member),)*
-                        _ => Err(Error::Codec(::failure::format_err!("Missing key: {}", key).into())),
-                    }
-                }
-            }
-        }
-        ::paste::item! {
-            #[doc(hidden)]
-            #[derive(Default)]
-            struct [<$name Builder>] {
-                $($member : Option<$value_type>,)*
-            }
-
-            impl [<$name Builder>] {
-                $(
-                    #[inline]
-                    fn [<set_ $member>](&mut self, value: $value_type) -> &mut Self {
-                        self.$member = Some(value);
-                        self
-                    }
-                )*
-
-                #[inline]
-                fn build(self) -> Result<$name, Error> {
-                    Ok($name {
-                        $($member: self.$member
-                            .ok_or(Error::Codec(::failure::format_err!("Missing key: {}", ::std::stringify!($member))))?,)*
-                    })
-                }
-            }
-        }
-
-        ::paste::item! {
-            impl<'a> TryFrom<Ipld<'a>> for $name
-            where
-                C: CodecExt,
-            {
-                type Error = Error;
-
-                #[inline]
-                fn try_from(ipld: Ipld<'a>) -> Result<Self, Self::Error> {
-                    match ipld {
-                        Ipld::Map(map_iter) => match map_iter {
-                            IpldMapIter::Vec(iter) => {
-                                let mut iter = iter.into_inner();
-                                let mut builder = [<$name Builder>]::default();
-
-                                while let Some((key, value)) = iter.next() {
-                                    match [<$name Builder>]::field(key)? {
-                                        $([<$name Field>]::[<Field $member>] => {
-                                            builder.[<set_ $member>]($value_type::try_from(value)?)
-                                        },)*
-                                    };
-                                };
-
-                                builder.build()
-                            }
-                        },
-                        _ => Err(Error::Ipld(IpldError::NotMap)),
-                    }
-                }
-            }
-        }
-
-        ::paste::item! {
-            impl<'a> TryInto<Ipld<'a>> for &'a $name
-            where
-                C: CodecExt,
-            {
-                type Error = Error;
-
-                #[inline]
-                fn try_into(self) -> Result<Ipld<'a>, Self::Error> {
-
-
-    //                let mut map: BTreeMap<&'static str, Ipld<'a>> = BTreeMap::new();
-    //                $(map.insert(::std::stringify!($member), (&(self.$member)).try_into()?);)*
-    //                Ok(Ipld::Map(map))
-                }
-            }
-        }
-    };
-    // (@count $t1:tt, $($t:tt),+) => { 1 + derive_ipld_for_struct!(@count $($t),+) };
-    // (@count $t:tt) => { 1 };
-}
-
-#[macro_export]
-macro_rules! derive_ipld_for_enum {
-    ($name:ident { $(| $member:ident,)* }) => {};
-}
-
-#[macro_export]
-macro_rules! derive_ipld_for_union {
-    ($name:ident { $($member:ident : $value_type:ty,)* }) => {};
-}
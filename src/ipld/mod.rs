@@ -1,4 +1,4 @@
 //! IPLD types.
 
 pub mod borrowed;
-// mod canon;
+mod canon;
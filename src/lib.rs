@@ -13,21 +13,31 @@
 mod _codecs;
 mod error;
 
+pub mod block;
+#[cfg(feature = "car")]
+pub mod car;
+pub mod ipld;
 pub mod representation;
 pub mod selectors;
+pub mod store;
 pub mod value;
 
+#[doc(inline)]
+pub use block::Block;
 #[doc(inline)]
 pub use _codecs::{Codec, Decoder, Encoder, IpldVisitorExt};
 #[doc(inline)]
 pub use error::Error;
-// pub use ipld::borrowed::Ipld as BorrowedIpld;
+#[doc(inline)]
+pub use ipld::borrowed::Ipld as BorrowedIpld;
 #[doc(inline)]
 pub use representation::{Context, Representation, Select};
 #[doc(inline)]
 pub use selectors::Selector;
-// #[doc(inline)]
-// pub use value::Value;
+#[doc(inline)]
+pub use store::BlockStore;
+#[doc(inline)]
+pub use value::Value;
 
 #[doc(inline)]
 pub use ipld_macros::{ipld_attr, schema, selector};
@@ -41,6 +51,21 @@ pub mod codecs {
 
     #[cfg(feature = "dag-json")]
     pub use crate::_codecs::dag_json::DagJson;
+    #[cfg(feature = "simd")]
+    pub use crate::_codecs::dag_json::SimdDagJson;
+
+    #[cfg(feature = "dag-pb")]
+    pub use crate::_codecs::dag_pb::{DagPb, PbLink, PbNode};
+
+    #[cfg(feature = "raw")]
+    pub use crate::_codecs::raw::Raw;
+
+    pub use crate::_codecs::identity::{
+        encode_identity_cid, identity_bytes, identity_cid, IDENTITY,
+    };
+    pub use crate::_codecs::multicodec::{
+        decode_any, encode_any, read_any, register, write_any,
+    };
 }
 
 /// All the exports and re-exports necessary for using `ipld`.
@@ -7,11 +7,29 @@
 //!     - Additionally, we can define an `impl State for Encrypted<R, W>: Context<R, W>` and a type whose `Representation` implementation could derive an encryption/decryption key from within the type, ensuring that the type can only be stored in ciphertext.
 
 use super::Representation;
+use crate::{dev::*, store::BlockStore};
 use async_trait::async_trait;
 
-///
+/// A source of the `Block`s that `Representation`s link to.
 #[async_trait]
-pub trait Context {
+pub trait Context<S = DefaultMultihashSize>: Send + Sync
+where
+    S: MultihashSize,
+{
+    /// Fetches a `Block` by its `CidGeneric`, failing with
+    /// `Error::BlockNotFound` by default.
+    async fn get_block(&self, cid: &CidGeneric<S>) -> Result<Block<S>, Error> {
+        Err(Error::BlockNotFound(cid.to_string()))
+    }
+
+    /// Stores a `Block`, failing by default.
+    async fn put_block(&self, block: Block<S>) -> Result<(), Error> {
+        Err(Error::BlockStore(anyhow::anyhow!(
+            "context cannot store block {}",
+            block.cid()
+        )))
+    }
+
     // /// Internally, this will:
     // ///     - get a (concrete?) BlockWriter from a BlockService
     // ///     - determine the Codec + Format from the BlockMeta
@@ -27,6 +45,23 @@ pub trait Context {
     //     B: Into<BlockMeta>;
 }
 
+#[async_trait]
+impl<S, B> Context<S> for B
+where
+    S: MultihashSize,
+    B: BlockStore<S>,
+{
+    #[inline]
+    async fn get_block(&self, cid: &CidGeneric<S>) -> Result<Block<S>, Error> {
+        self.get(cid).await
+    }
+
+    #[inline]
+    async fn put_block(&self, block: Block<S>) -> Result<(), Error> {
+        self.put(block).await
+    }
+}
+
 // impl<'a, Ctx: Context> Context for &'a Ctx {}
 
 pub trait ContextExt {}
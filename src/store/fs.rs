@@ -0,0 +1,179 @@
+use super::BlockStore;
+use crate::dev::*;
+use async_trait::async_trait;
+use blocking::unblock;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Counts the temporary files created by this process, so that concurrent
+/// `put`s of the same block never share a temporary file.
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The default number of characters used to name each shard directory.
+pub const DEFAULT_SHARD_LEN: usize = 2;
+
+/// A `BlockStore` that stores each `Block` as a flat file, named by its CID.
+///
+/// Blocks are sharded into subdirectories named by a prefix of their
+/// (base32-encoded) multihash digest, since the prefixes of CID strings are
+/// shared by all CIDs of the same version, codec and multihash.
+#[derive(Clone, Debug)]
+pub struct FsBlockStore {
+    root: PathBuf,
+    shard_len: usize,
+}
+
+impl FsBlockStore {
+    /// Creates a new `FsBlockStore` rooted at the given directory, creating the
+    /// directory if it doesn't exist.
+    #[inline]
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self, Error> {
+        Self::with_shard_len(root, DEFAULT_SHARD_LEN)
+    }
+
+    /// Creates a new `FsBlockStore`, whose shard directories are named by
+    /// `shard_len` characters of each block's multihash digest.
+    pub fn with_shard_len(root: impl Into<PathBuf>, shard_len: usize) -> Result<Self, Error> {
+        let root = root.into();
+        fs::create_dir_all(&root).map_err(|e| Error::BlockStore(anyhow::Error::new(e)))?;
+        Ok(Self { root, shard_len })
+    }
+
+    /// The store's root directory.
+    #[inline]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path of the file containing the block of the given CID.
+    pub fn path<S: MultihashSize>(&self, cid: &CidGeneric<S>) -> PathBuf {
+        let digest = Multibase::Base32Lower.encode(cid.hash().digest());
+        let shard_len = self.shard_len.min(digest.len());
+        self.root.join(&digest[..shard_len]).join(cid.to_string())
+    }
+}
+
+#[async_trait]
+impl<S: MultihashSize> BlockStore<S> for FsBlockStore {
+    async fn get(&self, cid: &CidGeneric<S>) -> Result<Block<S>, Error> {
+        let path = self.path(cid);
+        match unblock(move || fs::read(path)).await {
+            Ok(bytes) => Ok(Block::new_unchecked(cid.clone(), bytes)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                Err(Error::BlockNotFound(cid.to_string()))
+            }
+            Err(err) => Err(Error::BlockStore(anyhow::Error::new(err))),
+        }
+    }
+
+    /// Writes the block to a uniquely-named temporary file in its shard, then
+    /// renames it, so that partially-written blocks are never visible.
+    ///
+    /// As blocks are content-addressed, an existing file for the block is left
+    /// as-is.
+    async fn put(&self, block: Block<S>) -> Result<(), Error> {
+        block.verify()?;
+        let path = self.path(block.cid());
+        unblock(move || {
+            if path.is_file() {
+                return Ok(());
+            }
+
+            let shard = path.parent().expect("block paths are within a shard");
+            fs::create_dir_all(shard)?;
+
+            let tmp_id = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let tmp_path = path.with_extension(format!("{}.{}.tmp", process::id(), tmp_id));
+            fs::write(&tmp_path, block.bytes())?;
+            fs::rename(&tmp_path, &path).or_else(|err| {
+                let _ = fs::remove_file(&tmp_path);
+                // another `put` of the same block may have renamed it first
+                if path.is_file() {
+                    Ok(())
+                } else {
+                    Err(err)
+                }
+            })
+        })
+        .await
+        .map_err(|e| Error::BlockStore(anyhow::Error::new(e)))
+    }
+
+    async fn has(&self, cid: &CidGeneric<S>) -> Result<bool, Error> {
+        let path = self.path(cid);
+        match unblock(move || fs::metadata(path)).await {
+            Ok(metadata) => Ok(metadata.is_file()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(Error::BlockStore(anyhow::Error::new(err))),
+        }
+    }
+
+    async fn delete(&self, cid: &CidGeneric<S>) -> Result<(), Error> {
+        let path = self.path(cid);
+        match unblock(move || fs::remove_file(path)).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                Err(Error::BlockStore(anyhow::Error::new(err)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "dag-cbor"))]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_fs_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FsBlockStore::new(dir.path()).unwrap();
+        let block: Block = Block::encode::<DagCbor, _>(&String::from("hello"), 0x12).unwrap();
+        let cid = block.cid().clone();
+
+        let path = store.path(&cid);
+        assert_eq!(path.parent().unwrap().parent().unwrap(), dir.path());
+        assert_eq!(path.file_name().unwrap(), cid.to_string().as_str());
+
+        block_on(async {
+            assert!(!store.has(&cid).await.unwrap());
+            assert!(matches!(
+                store.get(&cid).await,
+                Err(Error::BlockNotFound(_))
+            ));
+
+            store.put(block.clone()).await.unwrap();
+            assert!(store.has(&cid).await.unwrap());
+            assert_eq!(store.get(&cid).await.unwrap(), block);
+
+            store.delete(&cid).await.unwrap();
+            assert!(!store.has(&cid).await.unwrap());
+            store.delete(&cid).await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_fs_store_concurrent_put() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FsBlockStore::new(dir.path()).unwrap();
+        let block: Block = Block::encode::<DagCbor, _>(&String::from("hello"), 0x12).unwrap();
+        let cid = block.cid().clone();
+
+        let puts = (0..8).map(|_| store.put(block.clone()));
+        block_on(async {
+            for res in futures::future::join_all(puts).await {
+                res.unwrap();
+            }
+            store.put(block.clone()).await.unwrap();
+            assert_eq!(store.get(&cid).await.unwrap(), block);
+        });
+
+        // only the block's file remains in its shard
+        let shard = store.path(&cid).parent().unwrap().to_owned();
+        assert_eq!(fs::read_dir(shard).unwrap().count(), 1);
+    }
+}
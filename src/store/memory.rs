@@ -0,0 +1,103 @@
+use super::BlockStore;
+use crate::dev::*;
+use async_trait::async_trait;
+use std::{collections::HashMap, sync::RwLock};
+
+/// An in-memory `BlockStore`.
+#[derive(Debug, Default)]
+pub struct MemoryBlockStore<S = DefaultMultihashSize>
+where
+    S: MultihashSize,
+{
+    blocks: RwLock<HashMap<CidGeneric<S>, bytes::Bytes>>,
+}
+
+impl<S: MultihashSize> MemoryBlockStore<S> {
+    /// Creates a new, empty `MemoryBlockStore`.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            blocks: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The number of `Block`s in the store.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.blocks.read().expect("block store poisoned").len()
+    }
+
+    /// Whether or not the store is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl<S: MultihashSize> BlockStore<S> for MemoryBlockStore<S> {
+    async fn get(&self, cid: &CidGeneric<S>) -> Result<Block<S>, Error> {
+        let blocks = self.blocks.read().expect("block store poisoned");
+        blocks
+            .get(cid)
+            .map(|bytes| Block::new_unchecked(cid.clone(), bytes.clone()))
+            .ok_or_else(|| Error::BlockNotFound(cid.to_string()))
+    }
+
+    async fn put(&self, block: Block<S>) -> Result<(), Error> {
+        block.verify()?;
+        let (cid, bytes) = block.into_inner();
+        self.blocks
+            .write()
+            .expect("block store poisoned")
+            .insert(cid, bytes);
+        Ok(())
+    }
+
+    async fn has(&self, cid: &CidGeneric<S>) -> Result<bool, Error> {
+        let blocks = self.blocks.read().expect("block store poisoned");
+        Ok(blocks.contains_key(cid))
+    }
+
+    async fn delete(&self, cid: &CidGeneric<S>) -> Result<(), Error> {
+        self.blocks
+            .write()
+            .expect("block store poisoned")
+            .remove(cid);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "dag-cbor"))]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_memory_store() {
+        let store: MemoryBlockStore = MemoryBlockStore::new();
+        let block: Block = Block::encode::<DagCbor, _>(&String::from("hello"), 0x12).unwrap();
+        let cid = block.cid().clone();
+
+        block_on(async {
+            assert!(!store.has(&cid).await.unwrap());
+            assert!(matches!(
+                store.get(&cid).await,
+                Err(Error::BlockNotFound(_))
+            ));
+
+            store.put(block.clone()).await.unwrap();
+            assert!(store.has(&cid).await.unwrap());
+            assert_eq!(store.get(&cid).await.unwrap(), block);
+            assert_eq!(store.len(), 1);
+
+            store.delete(&cid).await.unwrap();
+            assert!(!store.has(&cid).await.unwrap());
+            assert!(store.is_empty());
+
+            // unverified blocks are rejected
+            let invalid = Block::new_unchecked(cid.clone(), vec![0xf5]);
+            assert!(store.put(invalid).await.is_err());
+        });
+    }
+}
@@ -0,0 +1,30 @@
+//! Storage for IPLD `Block`s.
+
+mod fs;
+mod memory;
+
+pub use fs::FsBlockStore;
+pub use memory::MemoryBlockStore;
+
+use crate::dev::*;
+use async_trait::async_trait;
+
+/// An async store of `Block`s, addressed by their `CidGeneric`s.
+#[async_trait]
+pub trait BlockStore<S = DefaultMultihashSize>: Send + Sync
+where
+    S: MultihashSize,
+{
+    /// Gets a `Block` by its `CidGeneric`, failing with
+    /// `Error::BlockNotFound` if the store doesn't contain it.
+    async fn get(&self, cid: &CidGeneric<S>) -> Result<Block<S>, Error>;
+
+    /// Verifies and stores a `Block`.
+    async fn put(&self, block: Block<S>) -> Result<(), Error>;
+
+    /// Whether or not the store contains a `Block`.
+    async fn has(&self, cid: &CidGeneric<S>) -> Result<bool, Error>;
+
+    /// Deletes a `Block` from the store, if it exists.
+    async fn delete(&self, cid: &CidGeneric<S>) -> Result<(), Error>;
+}
@@ -0,0 +1,307 @@
+use crate::dev::*;
+use serde::de::{self, value::StringDeserializer, IntoDeserializer};
+use std::convert::TryFrom;
+
+/// Deserializes any `Deserialize` type from a `Value`, without encoding it to
+/// bytes.
+///
+/// Links are deserialized with `Decoder::deserialize_link`, and therefore can
+/// only be deserialized from `Value::Link`s.
+pub fn from_value<T, S>(value: Value<S>) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    S: MultihashSize,
+{
+    T::deserialize(value)
+}
+
+impl<S: MultihashSize> Value<S> {
+    #[inline]
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            Self::Null => de::Unexpected::Unit,
+            Self::Bool(b) => de::Unexpected::Bool(*b),
+            Self::Int(n) => match i64::try_from(*n) {
+                Ok(n) => de::Unexpected::Signed(n),
+                Err(_) => de::Unexpected::Other("integer"),
+            },
+            Self::Float(n) => de::Unexpected::Float(*n),
+            Self::String(s) => de::Unexpected::Str(s),
+            Self::Bytes(b) => de::Unexpected::Bytes(b),
+            Self::List(_) => de::Unexpected::Seq,
+            Self::Map(_) => de::Unexpected::Map,
+            Self::Link(_) => de::Unexpected::Other("link"),
+        }
+    }
+}
+
+impl<'de, S: MultihashSize> IntoDeserializer<'de, Error> for Value<S> {
+    type Deserializer = Self;
+
+    #[inline]
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+/// `Deserializer` implementation that visits the data model kind of the
+/// `Value`.
+///
+/// Links are visited as bytes, unless the visitor is deserialized with a
+/// `Decoder`, in which case they are visited with `IpldVisitorExt::visit_link`.
+impl<'de, S: MultihashSize> Deserializer<'de> for Value<S> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Null => visitor.visit_unit(),
+            Self::Bool(b) => visitor.visit_bool(b),
+            Self::Int(n) => match (i64::try_from(n), u64::try_from(n)) {
+                (Ok(n), _) => visitor.visit_i64(n),
+                (_, Ok(n)) => visitor.visit_u64(n),
+                _ => visitor.visit_i128(n),
+            },
+            Self::Float(n) => visitor.visit_f64(n),
+            Self::String(s) => visitor.visit_string(s),
+            Self::Bytes(b) => visitor.visit_byte_buf(b),
+            Self::List(list) => {
+                let mut seq = de::value::SeqDeserializer::new(list.into_iter());
+                let res = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(res)
+            }
+            Self::Map(map) => {
+                let mut map = de::value::MapDeserializer::new(map.into_iter());
+                let res = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(res)
+            }
+            Self::Link(cid) => visitor.visit_byte_buf(cid.to_bytes()),
+        }
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: Self::Null,
+            }),
+            Self::Map(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer { variant, value })
+            }
+            value => Err(de::Error::invalid_type(
+                value.unexpected(),
+                &"a string or single-key map",
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Visits `Value::Link`s as links and defers to the `Deserializer`
+/// implementation for everything else.
+impl<'de, S: MultihashSize> Decoder<'de> for Value<S> {
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: IpldVisitorExt<'de>,
+    {
+        match self {
+            Self::Link(cid) => visitor.visit_link(cid.to_bytes().into()),
+            value => Deserializer::deserialize_any(value, visitor),
+        }
+    }
+
+    #[inline]
+    fn deserialize_link<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: IpldVisitorExt<'de>,
+    {
+        match self {
+            Self::Link(cid) => visitor.visit_link(cid.to_bytes().into()),
+            value => Err(de::Error::invalid_type(value.unexpected(), &visitor)),
+        }
+    }
+}
+
+/// Deserializes an enum variant from a string, or from a single-entry map of
+/// the variant name to its contents.
+struct EnumDeserializer<S: MultihashSize> {
+    variant: String,
+    value: Value<S>,
+}
+
+impl<'de, S: MultihashSize> de::EnumAccess<'de> for EnumDeserializer<S> {
+    type Error = Error;
+    type Variant = Value<S>;
+
+    #[inline]
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(StringDeserializer::<Error>::new(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de, S: MultihashSize> de::VariantAccess<'de> for Value<S> {
+    type Error = Error;
+
+    #[inline]
+    fn unit_variant(self) -> Result<(), Error> {
+        match self {
+            Self::Null => Ok(()),
+            value => Err(de::Error::invalid_type(value.unexpected(), &"unit variant")),
+        }
+    }
+
+    #[inline]
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    #[inline]
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        Deserializer::deserialize_seq(self, visitor)
+    }
+
+    #[inline]
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Dag {
+        name: String,
+        size: Option<u64>,
+        data: Bytes,
+        link: Link<Null>,
+        list: Vec<Variant>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Variant {
+        Unit,
+        Newtype(i8),
+        Struct { f: bool },
+    }
+
+    fn cid() -> Cid {
+        Cid::try_from("bafyreidykglsfhoixmivffc5uwhcgshx4j465xwqntbmu43nb2dzqwfvae").unwrap()
+    }
+
+    fn dag() -> Dag {
+        Dag {
+            name: "dag".into(),
+            size: None,
+            data: vec![0u8, 1, 2].into(),
+            link: cid().into(),
+            list: vec![
+                Variant::Unit,
+                Variant::Newtype(-1),
+                Variant::Struct { f: true },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let value: Value = to_value(&dag()).unwrap();
+        assert_eq!(value["name"].as_str(), Some("dag"));
+        assert!(value["size"].is_null());
+        assert_eq!(value["data"].as_bytes(), Some(&[0u8, 1, 2][..]));
+        assert_eq!(value["link"].as_link(), Some(&cid()));
+        assert_eq!(value["list"][0].as_str(), Some("Unit"));
+        assert_eq!(value["list"][1]["Newtype"].as_int(), Some(-1));
+        assert_eq!(value["list"][2]["Struct"]["f"].as_bool(), Some(true));
+
+        assert_eq!(from_value::<Dag, _>(value.clone()).unwrap(), dag());
+        assert_eq!(from_value::<Value, _>(value.clone()).unwrap(), value);
+        assert_eq!(to_value::<_, DefaultMultihashSize>(&value).unwrap(), value);
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        struct Duplicates;
+
+        impl Serialize for Duplicates {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("a", &1)?;
+                map.serialize_entry("a", &2)?;
+                map.end()
+            }
+        }
+
+        assert!(to_value::<_, DefaultMultihashSize>(&Duplicates).is_err());
+    }
+
+    #[test]
+    fn test_link_from_bytes() {
+        let mut map = BTreeMap::new();
+        map.insert("link".into(), Value::Bytes(cid().to_bytes()));
+        let value: Value = Value::Map(map);
+        assert!(from_value::<BTreeMap<String, Link<Null>>, _>(value).is_err());
+    }
+}
@@ -0,0 +1,523 @@
+use crate::dev::*;
+use serde::de;
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fmt,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+/// An owned, dynamically-typed IPLD value, representing any of the nine IPLD
+/// data model kinds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<S = DefaultMultihashSize>
+where
+    S: MultihashSize,
+{
+    /// Represents the absence of a value.
+    Null,
+    /// Represents a boolean value.
+    Bool(bool),
+    /// Represents an integer.
+    Int(i128),
+    /// Represents a floating point number.
+    Float(f64),
+    /// Represents a UTF-8 string.
+    String(String),
+    /// Represents a sequence of bytes.
+    Bytes(Vec<u8>),
+    /// Represents a list.
+    List(Vec<Value<S>>),
+    /// Represents a map with string keys.
+    Map(BTreeMap<String, Value<S>>),
+    /// Represents a link to another dag.
+    Link(CidGeneric<S>),
+}
+
+impl<S: MultihashSize> Value<S> {
+    /// The IPLD data model kind of the value.
+    #[inline]
+    pub fn kind(&self) -> Kind {
+        match self {
+            Self::Null => Kind::Null,
+            Self::Bool(_) => Kind::Boolean,
+            Self::Int(_) => Kind::Integer,
+            Self::Float(_) => Kind::Float,
+            Self::String(_) => Kind::String,
+            Self::Bytes(_) => Kind::Bytes,
+            Self::List(_) => Kind::List,
+            Self::Map(_) => Kind::Map,
+            Self::Link(_) => Kind::Link,
+        }
+    }
+
+    /// Whether or not the value is `Null`.
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Returns the boolean, if the value is a `Bool`.
+    #[inline]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer, if the value is an `Int`.
+    #[inline]
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            Self::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the float, if the value is a `Float`.
+    #[inline]
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the string, if the value is a `String`.
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes, if the value is `Bytes`.
+    #[inline]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns the list, if the value is a `List`.
+    #[inline]
+    pub fn as_list(&self) -> Option<&Vec<Self>> {
+        match self {
+            Self::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns the map, if the value is a `Map`.
+    #[inline]
+    pub fn as_map(&self) -> Option<&BTreeMap<String, Self>> {
+        match self {
+            Self::Map(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Returns the `CidGeneric`, if the value is a `Link`.
+    #[inline]
+    pub fn as_link(&self) -> Option<&CidGeneric<S>> {
+        match self {
+            Self::Link(cid) => Some(cid),
+            _ => None,
+        }
+    }
+
+    /// Returns the value within a `List` at an index, or within a `Map` at a
+    /// key.
+    #[inline]
+    pub fn get<I: ValueIndex>(&self, index: I) -> Option<&Self> {
+        index.index_into(self)
+    }
+
+    /// Mutably returns the value within a `List` at an index, or within a `Map`
+    /// at a key.
+    #[inline]
+    pub fn get_mut<I: ValueIndex>(&mut self, index: I) -> Option<&mut Self> {
+        index.index_into_mut(self)
+    }
+}
+
+impl<S: MultihashSize> Representation for Value<S> {
+    const NAME: &'static str = "Value";
+}
+
+impl<S: MultihashSize> Default for Value<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::Null
+    }
+}
+
+/// A type that can index into a `Value`, i.e. a `usize` for `List`s, or a
+/// string for `Map`s.
+pub trait ValueIndex {
+    /// Returns the value at the index, if any.
+    fn index_into<'a, S: MultihashSize>(&self, value: &'a Value<S>) -> Option<&'a Value<S>>;
+
+    /// Mutably returns the value at the index, if any.
+    fn index_into_mut<'a, S: MultihashSize>(
+        &self,
+        value: &'a mut Value<S>,
+    ) -> Option<&'a mut Value<S>>;
+}
+
+impl ValueIndex for usize {
+    #[inline]
+    fn index_into<'a, S: MultihashSize>(&self, value: &'a Value<S>) -> Option<&'a Value<S>> {
+        match value {
+            Value::List(list) => list.get(*self),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn index_into_mut<'a, S: MultihashSize>(
+        &self,
+        value: &'a mut Value<S>,
+    ) -> Option<&'a mut Value<S>> {
+        match value {
+            Value::List(list) => list.get_mut(*self),
+            _ => None,
+        }
+    }
+}
+
+impl ValueIndex for str {
+    #[inline]
+    fn index_into<'a, S: MultihashSize>(&self, value: &'a Value<S>) -> Option<&'a Value<S>> {
+        match value {
+            Value::Map(map) => map.get(self),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn index_into_mut<'a, S: MultihashSize>(
+        &self,
+        value: &'a mut Value<S>,
+    ) -> Option<&'a mut Value<S>> {
+        match value {
+            Value::Map(map) => map.get_mut(self),
+            _ => None,
+        }
+    }
+}
+
+impl ValueIndex for String {
+    #[inline]
+    fn index_into<'a, S: MultihashSize>(&self, value: &'a Value<S>) -> Option<&'a Value<S>> {
+        self.as_str().index_into(value)
+    }
+
+    #[inline]
+    fn index_into_mut<'a, S: MultihashSize>(
+        &self,
+        value: &'a mut Value<S>,
+    ) -> Option<&'a mut Value<S>> {
+        self.as_str().index_into_mut(value)
+    }
+}
+
+impl<'i, I: ValueIndex + ?Sized> ValueIndex for &'i I {
+    #[inline]
+    fn index_into<'a, S: MultihashSize>(&self, value: &'a Value<S>) -> Option<&'a Value<S>> {
+        (**self).index_into(value)
+    }
+
+    #[inline]
+    fn index_into_mut<'a, S: MultihashSize>(
+        &self,
+        value: &'a mut Value<S>,
+    ) -> Option<&'a mut Value<S>> {
+        (**self).index_into_mut(value)
+    }
+}
+
+/// Panics if the value is not a `List` or `Map`, or if the index or key is not
+/// present.
+impl<I: ValueIndex, S: MultihashSize> Index<I> for Value<S> {
+    type Output = Self;
+
+    #[inline]
+    fn index(&self, index: I) -> &Self::Output {
+        self.get(index).expect("no value found at index")
+    }
+}
+
+/// Panics if the value is not a `List` or `Map`, or if the index or key is not
+/// present.
+impl<I: ValueIndex, S: MultihashSize> IndexMut<I> for Value<S> {
+    #[inline]
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        self.get_mut(index).expect("no value found at index")
+    }
+}
+
+macro_rules! impl_from {
+    ($($type:ty : $member:ident $(as $into:ty)?,)*) => {
+        $(
+            impl<S: MultihashSize> From<$type> for Value<S> {
+                #[inline]
+                fn from(t: $type) -> Self {
+                    Value::$member(t $(as $into)?)
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    bool: Bool,
+    i8: Int as i128,
+    i16: Int as i128,
+    i32: Int as i128,
+    i64: Int as i128,
+    i128: Int,
+    u8: Int as i128,
+    u16: Int as i128,
+    u32: Int as i128,
+    u64: Int as i128,
+    f32: Float as f64,
+    f64: Float,
+    String: String,
+    Vec<u8>: Bytes,
+    Vec<Value<S>>: List,
+    BTreeMap<String, Value<S>>: Map,
+    CidGeneric<S>: Link,
+}
+
+impl<'a, S: MultihashSize> From<&'a str> for Value<S> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Value::String(s.into())
+    }
+}
+
+/// `Serialize` implementation that delegates to an `Encoder` for bytes and
+/// links, otherwise directly calling methods on the `Serializer`.
+impl<S: MultihashSize> Serialize for Value<S> {
+    fn serialize<Se>(&self, serializer: Se) -> Result<Se::Ok, Se::Error>
+    where
+        Se: Serializer,
+    {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Int(n) => match (i64::try_from(*n), u64::try_from(*n)) {
+                (Ok(n), _) => serializer.serialize_i64(n),
+                (_, Ok(n)) => serializer.serialize_u64(n),
+                _ => serializer.serialize_i128(*n),
+            },
+            Self::Float(n) => serializer.serialize_f64(*n),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Bytes(b) => <Se as Encoder>::serialize_bytes(serializer, b),
+            Self::List(list) => serializer.collect_seq(list),
+            Self::Map(map) => serializer.collect_map(map),
+            Self::Link(cid) => <Se as Encoder>::serialize_link(serializer, cid),
+        }
+    }
+}
+
+/// `Deserialize` implementation that uses a `ValueVisitor` to visit Serde and
+/// IPLD data types.
+impl<'de, S: MultihashSize> Deserialize<'de> for Value<S> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <D as Decoder<'de>>::deserialize_any(deserializer, ValueVisitor(PhantomData))
+    }
+}
+
+/// `Visitor` for `Deserialize`ing a `Value`.
+struct ValueVisitor<S>(PhantomData<S>);
+
+macro_rules! visit_int {
+    ($($type:ty : $visit_fn:ident,)*) => {
+        $(
+            #[inline]
+            fn $visit_fn<E: de::Error>(self, n: $type) -> Result<Self::Value, E> {
+                Ok(Value::Int(n.into()))
+            }
+        )*
+    };
+}
+
+impl<'de, S: MultihashSize> Visitor<'de> for ValueVisitor<S> {
+    type Value = Value<S>;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid IPLD data type")
+    }
+
+    #[inline]
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Value::Null)
+    }
+
+    #[inline]
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Value::Null)
+    }
+
+    #[inline]
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <D as Decoder<'de>>::deserialize_any(deserializer, self)
+    }
+
+    #[inline]
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Self::Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    visit_int! {
+        i8: visit_i8,
+        i16: visit_i16,
+        i32: visit_i32,
+        i64: visit_i64,
+        i128: visit_i128,
+        u8: visit_u8,
+        u16: visit_u16,
+        u32: visit_u32,
+        u64: visit_u64,
+    }
+
+    #[inline]
+    fn visit_u128<E: de::Error>(self, n: u128) -> Result<Self::Value, E> {
+        i128::try_from(n)
+            .map(Value::Int)
+            .map_err(|_| E::invalid_value(de::Unexpected::Other("u128"), &self))
+    }
+
+    #[inline]
+    fn visit_f32<E: de::Error>(self, n: f32) -> Result<Self::Value, E> {
+        Ok(Value::Float(n.into()))
+    }
+
+    #[inline]
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Self::Value, E> {
+        Ok(Value::Float(n))
+    }
+
+    #[inline]
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(Value::String(s.into()))
+    }
+
+    #[inline]
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Self::Value, E> {
+        Ok(Value::String(s))
+    }
+
+    #[inline]
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(Value::Bytes(bytes.into()))
+    }
+
+    #[inline]
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Value::Bytes(bytes))
+    }
+
+    #[inline]
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut list = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element()? {
+            list.push(value);
+        }
+        Ok(Value::List(list))
+    }
+
+    #[inline]
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let mut values = BTreeMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if values.contains_key(&key) {
+                return Err(de::Error::custom(format!("duplicate map key `{}`", key)));
+            }
+            values.insert(key, map.next_value()?);
+        }
+        Ok(Value::Map(values))
+    }
+}
+
+impl<'de, S: MultihashSize> IpldVisitorExt<'de> for ValueVisitor<S> {
+    #[inline]
+    fn visit_link<E: de::Error>(self, cid_bytes: Box<[u8]>) -> Result<Self::Value, E> {
+        let cid = CidGeneric::<S>::try_from(cid_bytes.as_ref())
+            .map_err(|err| E::custom(Error::Cid(err)))?;
+        Ok(Value::Link(cid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value() -> Value {
+        let cid =
+            Cid::try_from("bafyreidykglsfhoixmivffc5uwhcgshx4j465xwqntbmu43nb2dzqwfvae").unwrap();
+        let mut map = BTreeMap::new();
+        map.insert("null".into(), Value::Null);
+        map.insert("bool".into(), true.into());
+        map.insert("int".into(), (-3i64).into());
+        map.insert("big".into(), u64::max_value().into());
+        map.insert("float".into(), 1.5.into());
+        map.insert("string".into(), "hello".into());
+        map.insert("bytes".into(), vec![0u8, 1, 2].into());
+        map.insert("link".into(), cid.into());
+        map.insert("list".into(), vec![Value::from(1u8), "two".into()].into());
+        Value::Map(map)
+    }
+
+    #[test]
+    fn test_index() {
+        let mut value = value();
+        assert_eq!(value.kind(), Kind::Map);
+        assert_eq!(value["int"].as_int(), Some(-3));
+        assert_eq!(value["list"][1].as_str(), Some("two"));
+        assert_eq!(value.get("list").and_then(|l| l.get(2)), None);
+        assert_eq!(value.get(0), None);
+        assert!(value["link"].as_link().is_some());
+
+        value["list"][0] = Value::Null;
+        assert!(value["list"][0].is_null());
+    }
+
+    #[test]
+    #[cfg(feature = "dag-cbor")]
+    fn test_dag_cbor() {
+        let mut bytes = Vec::new();
+        DagCbor::write(&value(), &mut bytes).unwrap();
+        assert_eq!(DagCbor::decode::<Value>(&bytes).unwrap(), value());
+    }
+
+    #[test]
+    #[cfg(feature = "dag-json")]
+    fn test_dag_json() {
+        let mut bytes = Vec::new();
+        DagJson::write(&value(), &mut bytes).unwrap();
+        assert_eq!(DagJson::decode::<Value>(&bytes).unwrap(), value());
+
+        let json = br#"{"a":1,"b":2,"a":3}"#;
+        assert!(DagJson::decode::<Value>(json).is_err());
+    }
+}
@@ -1,10 +1,10 @@
 use crate::dev::*;
-use std::{convert::TryFrom, marker::PhantomData, rc::Rc};
+use std::{collections::BTreeMap, convert::TryFrom, marker::PhantomData, rc::Rc};
 
 /// Link type, used to switch between a `cid::CidGeneric` and it's underlying dag.
 ///
-/// Under the hood, `Link` uses a `std::cell::Cell` in order to load links while
-/// reading into the dag.
+/// A `Link` starts out as a `cid::CidGeneric`, and caches it's dag once
+/// resolved against a `Context`.
 /// TODO: impl Serialize for Link, checking if impls!(S: Encoder)
 #[derive(Debug, Eq, PartialEq)]
 pub struct Link<T, S = DefaultMultihashSize>(InnerLink<T, S>)
@@ -26,9 +26,298 @@ where
         cid: CidGeneric<S>,
         // selector: Rc<Selector>,
         dag: T,
+        /// Whether or not the dag may have been modified since it was resolved.
+        dirty: bool,
     },
 }
 
+impl<T, S> Link<T, S>
+where
+    T: Representation,
+    S: MultihashSize,
+{
+    /// Returns the `cid::CidGeneric` of the linked dag.
+    ///
+    /// For dirty links, this is the CID of the dag as of its last flush.
+    #[inline]
+    pub fn cid(&self) -> &CidGeneric<S> {
+        match &self.0 {
+            InnerLink::Cid(cid) | InnerLink::Selection { cid, .. } => cid,
+        }
+    }
+
+    /// Whether or not the linked dag has been resolved.
+    #[inline]
+    pub fn is_resolved(&self) -> bool {
+        match &self.0 {
+            InnerLink::Cid(_) => false,
+            InnerLink::Selection { .. } => true,
+        }
+    }
+
+    /// Whether or not the linked dag has been mutably borrowed since it was
+    /// resolved or last flushed.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        match &self.0 {
+            InnerLink::Cid(_) => false,
+            InnerLink::Selection { dirty, .. } => *dirty,
+        }
+    }
+
+    /// Returns the resolved dag, if any.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        match &self.0 {
+            InnerLink::Cid(_) => None,
+            InnerLink::Selection { dag, .. } => Some(dag),
+        }
+    }
+
+    /// Returns the resolved dag, if any, marking the link as dirty.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        match &mut self.0 {
+            InnerLink::Cid(_) => None,
+            InnerLink::Selection { dag, dirty, .. } => {
+                *dirty = true;
+                Some(dag)
+            }
+        }
+    }
+
+    /// Resolves the linked dag, fetching its `Block` from the `Context` and
+    /// decoding it with the codec of its `cid::CidGeneric`.
+    ///
+    /// The dag is cached within the link, so subsequent calls return it without
+    /// fetching or decoding the block again.
+    pub async fn resolve<Ctx>(&mut self, ctx: &Ctx) -> Result<&T, Error>
+    where
+        Ctx: Context<S>,
+        T: DeserializeOwned + 'static,
+    {
+        self.load(ctx).await?;
+        Ok(self.get().expect("link was just resolved"))
+    }
+
+    /// Resolves the linked dag, marking the link as dirty.
+    pub async fn resolve_mut<Ctx>(&mut self, ctx: &Ctx) -> Result<&mut T, Error>
+    where
+        Ctx: Context<S>,
+        T: DeserializeOwned + 'static,
+    {
+        self.load(ctx).await?;
+        Ok(self.get_mut().expect("link was just resolved"))
+    }
+
+    /// Encodes and stores the linked dag if it, or any of its own links, are
+    /// dirty, updating the link's `cid::CidGeneric`.
+    ///
+    /// Dirty links are flushed bottom-up, so that each dag is encoded with the
+    /// updated CIDs of its children. Each dag is re-encoded with the codec and
+    /// multihash of its previous CID.
+    pub async fn flush<Ctx>(&mut self, ctx: &Ctx) -> Result<&CidGeneric<S>, Error>
+    where
+        Ctx: Context<S>,
+        T: Serialize + 'static,
+    {
+        let mut blocks = Vec::new();
+        self.flush_blocks(&mut blocks)?;
+        for block in blocks {
+            ctx.put_block(block).await?;
+        }
+        Ok(self.cid())
+    }
+
+    async fn load<Ctx>(&mut self, ctx: &Ctx) -> Result<(), Error>
+    where
+        Ctx: Context<S>,
+        T: DeserializeOwned + 'static,
+    {
+        if let InnerLink::Cid(cid) = &self.0 {
+            let cid = cid.clone();
+            check_target::<T, S>(&cid)?;
+            let dag = ctx
+                .get_block(&cid)
+                .await?
+                .decode()
+                .map_err(|err| Error::invalid_link_target::<T, S>(&cid, err))?;
+            self.0 = InnerLink::Selection {
+                cid,
+                dag,
+                dirty: false,
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Constraints on the CIDs a `Link` to this type may resolve, checked before
+/// fetching the linked `Block`.
+///
+/// By default, links may resolve CIDs of any codec and multihash. Types can
+/// opt-in to stricter checks by overriding these constants:
+///
+/// ```ignore
+/// impl LinkTarget for MyType {
+///     const CODECS: Option<&'static [u64]> = Some(&[DagCbor::CODE]);
+///     const MIN_HASH_BITS: usize = 256;
+/// }
+/// ```
+pub trait LinkTarget: Representation {
+    /// The multicodec codes the linked `Block` may be encoded with, if
+    /// restricted.
+    const CODECS: Option<&'static [u64]>;
+
+    /// The minimum length, in bits, of the CID's multihash digest. Identity
+    /// multihashes are considered to have no strength.
+    const MIN_HASH_BITS: usize;
+}
+
+impl<T: Representation> LinkTarget for T {
+    default const CODECS: Option<&'static [u64]> = None;
+    default const MIN_HASH_BITS: usize = 0;
+}
+
+fn check_target<T, S>(cid: &CidGeneric<S>) -> Result<(), Error>
+where
+    T: Representation,
+    S: MultihashSize,
+{
+    if let Some(codecs) = <T as LinkTarget>::CODECS {
+        if !codecs.contains(&cid.codec()) {
+            let reason = format!("codec {:#x} is not allowed", cid.codec());
+            return Err(Error::invalid_link_target::<T, S>(cid, reason));
+        }
+    }
+
+    let hash_bits = match cid.hash().code() {
+        codecs::IDENTITY => 0,
+        _ => cid.hash().digest().len() * 8,
+    };
+    if hash_bits < <T as LinkTarget>::MIN_HASH_BITS {
+        let reason = format!(
+            "{}-bit multihash is weaker than the minimum of {} bits",
+            hash_bits,
+            <T as LinkTarget>::MIN_HASH_BITS
+        );
+        return Err(Error::invalid_link_target::<T, S>(cid, reason));
+    }
+    Ok(())
+}
+
+/// Encodes the dirty `Link`s within a dag into `Block`s.
+///
+/// Implemented by default as a no-op, and specialized for `Link`s, lists, maps,
+/// optionals, boxes and `schema!` types to visit their links.
+pub trait Flush<S = DefaultMultihashSize>
+where
+    S: MultihashSize,
+{
+    /// Encodes each dirty `Link` (and each `Link` whose dag contains updated
+    /// links) into a `Block`, children first, updating the link's
+    /// `cid::CidGeneric`.
+    ///
+    /// Returns whether or not any `cid::CidGeneric` within the dag changed.
+    fn flush_blocks(&mut self, blocks: &mut Vec<Block<S>>) -> Result<bool, Error>;
+}
+
+impl<T, S> Flush<S> for T
+where
+    S: MultihashSize,
+{
+    #[inline]
+    default fn flush_blocks(&mut self, _: &mut Vec<Block<S>>) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+impl<T, S> Flush<S> for Link<T, S>
+where
+    T: Representation + Serialize + 'static,
+    S: MultihashSize,
+{
+    fn flush_blocks(&mut self, blocks: &mut Vec<Block<S>>) -> Result<bool, Error> {
+        let (cid, dag, dirty) = match &mut self.0 {
+            InnerLink::Cid(_) => return Ok(false),
+            InnerLink::Selection { cid, dag, dirty } => (cid, dag, dirty),
+        };
+
+        let children_changed = dag.flush_blocks(blocks)?;
+        if !*dirty && !children_changed {
+            return Ok(false);
+        }
+
+        let block = Block::encode_any(cid.codec(), &*dag, cid.hash().code())?;
+        // keep the original CID version, e.g. of CIDv0 `DagPb` links
+        let block = match cid.version() {
+            cid::Version::V1 => block,
+            version => {
+                let (new_cid, bytes) = block.into_inner();
+                let new_cid = CidGeneric::new(version, new_cid.codec(), new_cid.hash().clone())?;
+                Block::new_unchecked(new_cid, bytes)
+            }
+        };
+        let changed = block.cid() != cid;
+        *cid = block.cid().clone();
+        *dirty = false;
+        blocks.push(block);
+        Ok(changed)
+    }
+}
+
+impl<T, S> Flush<S> for Vec<T>
+where
+    T: Flush<S>,
+    S: MultihashSize,
+{
+    fn flush_blocks(&mut self, blocks: &mut Vec<Block<S>>) -> Result<bool, Error> {
+        let mut changed = false;
+        for item in self.iter_mut() {
+            changed |= item.flush_blocks(blocks)?;
+        }
+        Ok(changed)
+    }
+}
+
+impl<K, V, S> Flush<S> for BTreeMap<K, V>
+where
+    V: Flush<S>,
+    S: MultihashSize,
+{
+    fn flush_blocks(&mut self, blocks: &mut Vec<Block<S>>) -> Result<bool, Error> {
+        let mut changed = false;
+        for value in self.values_mut() {
+            changed |= value.flush_blocks(blocks)?;
+        }
+        Ok(changed)
+    }
+}
+
+impl<T, S> Flush<S> for Box<T>
+where
+    T: Flush<S>,
+    S: MultihashSize,
+{
+    #[inline]
+    fn flush_blocks(&mut self, blocks: &mut Vec<Block<S>>) -> Result<bool, Error> {
+        (**self).flush_blocks(blocks)
+    }
+}
+
+impl<T, S> Flush<S> for Option<T>
+where
+    T: Flush<S>,
+    S: MultihashSize,
+{
+    fn flush_blocks(&mut self, blocks: &mut Vec<Block<S>>) -> Result<bool, Error> {
+        match self {
+            Some(t) => t.flush_blocks(blocks),
+            None => Ok(false),
+        }
+    }
+}
+
 impl<T, S> Representation for Link<T, S>
 where
     T: Representation,
@@ -183,9 +472,12 @@ where
         Se: Serializer,
     {
         match &self.0 {
-            InnerLink::Cid(cid) => <Se as Encoder>::serialize_link(serializer, cid),
-            _ => Err(<Se::Error as serde::ser::Error>::custom(
-                "cannot serialize IPLD selection",
+            InnerLink::Cid(cid)
+            | InnerLink::Selection {
+                cid, dirty: false, ..
+            } => <Se as Encoder>::serialize_link(serializer, cid),
+            InnerLink::Selection { cid, .. } => Err(<Se::Error as serde::ser::Error>::custom(
+                format!("cannot serialize dirty IPLD link {}, flush it first", cid),
             )),
         }
     }
@@ -231,7 +523,8 @@ where
             where
                 E: serde::de::Error,
             {
-                let cid = CidGeneric::<S>::try_from(cid_bytes.as_ref()).map_err(E::custom)?;
+                let cid = CidGeneric::<S>::try_from(cid_bytes.as_ref())
+                    .map_err(|err| E::custom(Error::Cid(err)))?;
                 Ok(Link::from(cid))
             }
         }
@@ -263,3 +556,217 @@ where
 //     <H as TryFrom<u64>>::Error: Debug,
 // {
 // }
+
+#[cfg(all(test, feature = "dag-cbor"))]
+mod tests {
+    use super::*;
+    use crate::store::MemoryBlockStore;
+    use futures::executor::block_on;
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        type Node struct {
+            name String,
+            children Vec<Link<Node>>,
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        type Strict struct {
+            name String,
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        type Wrapper<T> struct {
+            inner T,
+        };
+    }
+
+    schema! {
+        #[ipld_attr(internal)]
+        #[derive(Debug, PartialEq)]
+        type Tree union {
+            | Node "node"
+        } representation keyed;
+    }
+
+    impl LinkTarget for Strict {
+        const CODECS: Option<&'static [u64]> = Some(&[DagCbor::CODE]);
+        const MIN_HASH_BITS: usize = 512;
+    }
+
+    fn put_node(store: &MemoryBlockStore, name: &str, children: Vec<Cid>) -> Cid {
+        let node = Node {
+            name: name.into(),
+            children: children.into_iter().map(Link::from).collect(),
+        };
+        let block = Block::encode::<DagCbor, _>(&node, 0x12).unwrap();
+        let cid = block.cid().clone();
+        block_on(store.put(block)).unwrap();
+        cid
+    }
+
+    #[test]
+    fn test_resolve() {
+        block_on(async {
+            let store = MemoryBlockStore::new();
+            let block = Block::encode::<DagCbor, _>(&String::from("hello"), 0x12).unwrap();
+            store.put(block.clone()).await.unwrap();
+
+            let mut link: Link<String> = Link::from(block.cid().clone());
+            assert!(!link.is_resolved());
+            assert_eq!(link.resolve(&store).await.unwrap(), "hello");
+            assert!(link.is_resolved());
+            assert_eq!(link.cid(), block.cid());
+
+            // the resolved dag is cached
+            let empty: MemoryBlockStore = MemoryBlockStore::new();
+            assert_eq!(link.resolve(&empty).await.unwrap(), "hello");
+
+            let mut missing: Link<String> = Link::from(block.cid().clone());
+            match missing.resolve(&empty).await {
+                Err(Error::BlockNotFound(_)) => {}
+                res => panic!("expected BlockNotFound, got {:?}", res),
+            }
+            assert!(!missing.is_resolved());
+        });
+    }
+
+    #[test]
+    fn test_flush() {
+        let store = MemoryBlockStore::new();
+        let leaf = put_node(&store, "leaf", vec![]);
+        let root = put_node(&store, "root", vec![leaf.clone()]);
+
+        block_on(async {
+            // flushing a clean link is a no-op
+            let mut link: Link<Node> = Link::from(root.clone());
+            link.resolve(&store).await.unwrap();
+            assert_eq!(link.flush(&store).await.unwrap(), &root);
+            assert!(DagCbor::write(&link, Vec::new()).is_ok());
+
+            // only the dirty leaf is modified, but both blocks are rewritten
+            let child = &mut link.get_mut().unwrap().children[0];
+            child.resolve_mut(&store).await.unwrap().name = "new leaf".into();
+            assert!(child.is_dirty());
+            assert!(DagCbor::write(&link, Vec::new()).is_err());
+
+            let new_root = link.flush(&store).await.unwrap().clone();
+            assert_ne!(new_root, root);
+            assert!(!link.is_dirty());
+            assert_eq!(store.len(), 4);
+
+            let mut link: Link<Node> = Link::from(new_root);
+            let node = link.resolve_mut(&store).await.unwrap();
+            assert_eq!(node.name, "root");
+            assert_ne!(node.children[0].cid(), &leaf);
+            let child = node.children[0].resolve(&store).await.unwrap();
+            assert_eq!(child.name, "new leaf");
+        });
+    }
+
+    #[test]
+    fn test_flush_members() {
+        let store = MemoryBlockStore::new();
+        let leaf = put_node(&store, "leaf", vec![]);
+
+        block_on(async {
+            let mut blocks: Vec<Block> = Vec::new();
+
+            // links within generic structs are flushed
+            let mut wrapper = Wrapper {
+                inner: Link::<Node>::from(leaf.clone()),
+            };
+            wrapper.inner.resolve_mut(&store).await.unwrap().name = "new leaf".into();
+            assert!(wrapper.flush_blocks(&mut blocks).unwrap());
+            assert!(!wrapper.inner.is_dirty());
+            assert_ne!(wrapper.inner.cid(), &leaf);
+
+            // links within union members are flushed
+            let mut tree = Tree::Node(Node {
+                name: "root".into(),
+                children: vec![Link::from(leaf.clone())],
+            });
+            if let Tree::Node(node) = &mut tree {
+                node.children[0].resolve_mut(&store).await.unwrap().name = "new leaf".into();
+            }
+            assert!(tree.flush_blocks(&mut blocks).unwrap());
+            let Tree::Node(node) = &tree;
+            assert!(!node.children[0].is_dirty());
+            assert_eq!(node.children[0].cid(), wrapper.inner.cid());
+            assert_eq!(blocks.len(), 2);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "dag-pb")]
+    fn test_flush_cid_v0() {
+        let store = MemoryBlockStore::new();
+        let node = PbNode {
+            links: vec![],
+            data: Some(vec![1u8].into()),
+        };
+        let (cid, bytes) = Block::encode::<DagPb, _>(&node, 0x12).unwrap().into_inner();
+        let cid = Cid::new_v0(cid.hash().clone()).unwrap();
+        block_on(store.put(Block::new_unchecked(cid.clone(), bytes))).unwrap();
+
+        block_on(async {
+            let mut link: Link<PbNode> = Link::from(cid.clone());
+            link.resolve_mut(&store).await.unwrap().data = Some(vec![2u8].into());
+            let new_cid = link.flush(&store).await.unwrap();
+            assert_ne!(new_cid, &cid);
+            assert_eq!(new_cid.version(), cid::Version::V0);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "dag-json")]
+    fn test_link_target() {
+        const SHA2_256: u64 = 0x12;
+        const SHA2_512: u64 = 0x13;
+
+        let store = MemoryBlockStore::new();
+        let put = |block: Block| {
+            let cid = block.cid().clone();
+            block_on(store.put(block)).unwrap();
+            cid
+        };
+        let strict = Strict {
+            name: "strict".into(),
+        };
+        let valid = put(Block::encode::<DagCbor, _>(&strict, SHA2_512).unwrap());
+        let weak = put(Block::encode::<DagCbor, _>(&strict, SHA2_256).unwrap());
+        let json = put(Block::encode::<DagJson, _>(&strict, SHA2_512).unwrap());
+        let string = put(Block::encode::<DagCbor, _>(&String::from("str"), SHA2_512).unwrap());
+
+        block_on(async {
+            let mut link: Link<Strict> = Link::from(valid);
+            assert_eq!(link.resolve(&store).await.unwrap(), &strict);
+
+            for cid in vec![weak, json, string] {
+                let mut link: Link<Strict> = Link::from(cid.clone());
+                match link.resolve(&store).await {
+                    Err(Error::InvalidLinkTarget {
+                        type_name,
+                        cid: cid_str,
+                        ..
+                    }) => {
+                        assert_eq!(type_name, "Strict");
+                        assert_eq!(cid_str, cid.to_string());
+                    }
+                    res => panic!("expected InvalidLinkTarget, got {:?}", res),
+                }
+            }
+
+            // types without constraints accept any codec or multihash
+            let mut link: Link<Node> = Link::from(put_node(&store, "node", vec![]));
+            assert!(link.resolve(&store).await.is_ok());
+        });
+    }
+}
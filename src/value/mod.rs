@@ -2,35 +2,27 @@
 
 // pub mod borrowed;
 // mod canon;
+mod de;
+mod kinded;
 mod link;
 mod list;
 mod map;
 mod primitive;
 mod recursive;
+mod scalar;
+mod ser;
 
-pub use link::Link;
+pub use de::from_value;
+pub use kinded::{Value, ValueIndex};
+pub use link::{Flush, Link, LinkTarget};
 pub use list::List;
 pub use map::Map;
+pub use scalar::{from_delimited, to_delimited};
+pub use ser::{to_value, ValueSerializer};
 
 use crate::dev::*;
 use macros::derive_more::{Add, AsRef, From, Into, Mul, Sum};
 
-// schema! {
-//     ///
-//     #[ipld_attr(internal)]
-//     pub type Value union {
-//         | Null null
-//         | Bool bool
-//         | Int int
-//         | Float float
-//         | String string
-//         | Bytes bytes
-//         | List list
-//         | Map map
-//         | Link link
-//     } representation kinded;
-// }
-
 schema! {
     /// The `null` type.
     #[ipld_attr(internal)]
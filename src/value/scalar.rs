@@ -0,0 +1,160 @@
+use crate::dev::*;
+use serde::de::{self, value::BorrowedStrDeserializer};
+
+/// Serializes a scalar (i.e. a boolean, number or string) as a component of a
+/// delimited string representation, failing if the component contains any of
+/// the delimiters.
+///
+/// `name` describes the component in any returned error.
+pub fn to_delimited<T>(dag: &T, name: &str, delims: &[&str]) -> Result<String, Error>
+where
+    T: Serialize + ?Sized,
+{
+    let s = match to_value::<_, DefaultMultihashSize>(dag)? {
+        Value::Bool(b) => b.to_string(),
+        Value::Int(n) => n.to_string(),
+        Value::Float(n) => n.to_string(),
+        Value::String(s) => s,
+        value => {
+            return Err(Error::Encoder(anyhow::anyhow!(
+                "`{}` must be a boolean, number or string, found {:?}",
+                name,
+                value.kind(),
+            )))
+        }
+    };
+
+    match delims.iter().find(|delim| s.contains(*delim)) {
+        Some(delim) => Err(Error::Encoder(anyhow::anyhow!(
+            "`{}` value {:?} contains the delimiter {:?}",
+            name,
+            s,
+            delim,
+        ))),
+        None => Ok(s),
+    }
+}
+
+/// Parses a scalar from a component of a delimited string representation.
+///
+/// `name` describes the component in any returned error.
+pub fn from_delimited<'a, T>(s: &'a str, name: &str) -> Result<T, Error>
+where
+    T: Deserialize<'a>,
+{
+    T::deserialize(ScalarDeserializer(s)).map_err(|err| {
+        Error::Decoder(anyhow::anyhow!(
+            "`{}` could not be parsed from {:?}: {}",
+            name,
+            s,
+            err
+        ))
+    })
+}
+
+/// Deserializes a scalar by parsing a string as whichever type is requested.
+struct ScalarDeserializer<'a>(&'a str);
+
+macro_rules! deserialize_parsed {
+    ($($type:ty : $deserialize_fn:ident $visit_fn:ident,)*) => {
+        $(
+            #[inline]
+            fn $deserialize_fn<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let n = self.0.parse::<$type>().map_err(de::Error::custom)?;
+                visitor.$visit_fn(n)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ScalarDeserializer<'de> {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_parsed! {
+        bool: deserialize_bool visit_bool,
+        i8: deserialize_i8 visit_i8,
+        i16: deserialize_i16 visit_i16,
+        i32: deserialize_i32 visit_i32,
+        i64: deserialize_i64 visit_i64,
+        i128: deserialize_i128 visit_i128,
+        u8: deserialize_u8 visit_u8,
+        u16: deserialize_u16 visit_u16,
+        u32: deserialize_u32 visit_u32,
+        u64: deserialize_u64 visit_u64,
+        u128: deserialize_u128 visit_u128,
+        f32: deserialize_f32 visit_f32,
+        f64: deserialize_f64 visit_f64,
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(BorrowedStrDeserializer::new(self.0))
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delimited() {
+        assert_eq!(to_delimited(&Int::from(-3), "a", &[","]).unwrap(), "-3");
+        assert_eq!(to_delimited(&true, "a", &[","]).unwrap(), "true");
+        assert_eq!(from_delimited::<Int>("-3", "a").unwrap(), Int::from(-3));
+        assert_eq!(
+            from_delimited::<Float>("1.5", "a").unwrap(),
+            Float::from(1.5)
+        );
+        assert_eq!(from_delimited::<String>("a b", "a").unwrap(), "a b");
+
+        let err = to_delimited("a,b", "field", &["=", ","]).unwrap_err();
+        assert!(err.to_string().contains("`field`"));
+        assert!(to_delimited(&vec![1], "a", &[","]).is_err());
+        let err = from_delimited::<Int>("one", "field").unwrap_err();
+        assert!(err.to_string().contains("`field`"));
+    }
+}
@@ -0,0 +1,585 @@
+use crate::dev::*;
+use serde::ser::{self, Impossible};
+use std::{collections::BTreeMap, convert::TryFrom, marker::PhantomData};
+
+/// Serializes any `Serialize` type into a `Value`, without encoding it to
+/// bytes.
+///
+/// Links are serialized with `Encoder::serialize_link`, and therefore become
+/// `Value::Link`s rather than `Value::Bytes`.
+pub fn to_value<T, S>(dag: &T) -> Result<Value<S>, Error>
+where
+    T: Serialize + ?Sized,
+    S: MultihashSize,
+{
+    dag.serialize(ValueSerializer::new())
+}
+
+/// A `Serializer` whose output is a `Value`.
+#[derive(Debug)]
+pub struct ValueSerializer<S = DefaultMultihashSize>(PhantomData<S>);
+
+impl<S: MultihashSize> ValueSerializer<S> {
+    /// Creates a new `ValueSerializer`.
+    #[inline]
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S: MultihashSize> Default for ValueSerializer<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: MultihashSize> Encoder for ValueSerializer<S> {
+    #[inline]
+    fn serialize_bytes(self, bytes: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Bytes(bytes.into()))
+    }
+
+    #[inline]
+    fn serialize_link<S2>(self, cid: &CidGeneric<S2>) -> Result<Self::Ok, Self::Error>
+    where
+        S2: MultihashSize,
+    {
+        let cid = CidGeneric::try_from(cid.to_bytes().as_slice())?;
+        Ok(Value::Link(cid))
+    }
+}
+
+macro_rules! serialize_int {
+    ($($type:ty : $serialize_fn:ident,)*) => {
+        $(
+            #[inline]
+            fn $serialize_fn(self, n: $type) -> Result<Self::Ok, Self::Error> {
+                Ok(Value::Int(n.into()))
+            }
+        )*
+    };
+}
+
+impl<S: MultihashSize> Serializer for ValueSerializer<S> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList<S>;
+    type SerializeTuple = SerializeList<S>;
+    type SerializeTupleStruct = SerializeList<S>;
+    type SerializeTupleVariant = SerializeVariant<SerializeList<S>>;
+    type SerializeMap = SerializeMap<S>;
+    type SerializeStruct = SerializeMap<S>;
+    type SerializeStructVariant = SerializeVariant<SerializeMap<S>>;
+
+    #[inline]
+    fn serialize_bool(self, b: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Bool(b))
+    }
+
+    serialize_int! {
+        i8: serialize_i8,
+        i16: serialize_i16,
+        i32: serialize_i32,
+        i64: serialize_i64,
+        i128: serialize_i128,
+        u8: serialize_u8,
+        u16: serialize_u16,
+        u32: serialize_u32,
+        u64: serialize_u64,
+    }
+
+    #[inline]
+    fn serialize_u128(self, n: u128) -> Result<Self::Ok, Self::Error> {
+        i128::try_from(n)
+            .map(Value::Int)
+            .map_err(|_| <Error as ser::Error>::custom("u128 out of range for an IPLD int"))
+    }
+
+    #[inline]
+    fn serialize_f32(self, n: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Float(n.into()))
+    }
+
+    #[inline]
+    fn serialize_f64(self, n: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Float(n))
+    }
+
+    #[inline]
+    fn serialize_char(self, c: char) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::String(c.to_string()))
+    }
+
+    #[inline]
+    fn serialize_str(self, s: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::String(s.into()))
+    }
+
+    #[inline]
+    fn serialize_bytes(self, bytes: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Bytes(bytes.into()))
+    }
+
+    #[inline]
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    #[inline]
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    #[inline]
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Null)
+    }
+
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::String(variant.into()))
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Ok(variant_map(variant, value.serialize(self)?))
+    }
+
+    #[inline]
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SerializeList(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    #[inline]
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Ok(SerializeVariant(variant, self.serialize_seq(Some(len))?))
+    }
+
+    #[inline]
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeMap(BTreeMap::new(), None))
+    }
+
+    #[inline]
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    #[inline]
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Ok(SerializeVariant(variant, self.serialize_map(Some(len))?))
+    }
+}
+
+/// Serializes a list of `Value`s.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SerializeList<S: MultihashSize>(Vec<Value<S>>);
+
+impl<S: MultihashSize> ser::SerializeSeq for SerializeList<S> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.0.push(to_value(value)?);
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::List(self.0))
+    }
+}
+
+impl<S: MultihashSize> ser::SerializeTuple for SerializeList<S> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<S: MultihashSize> ser::SerializeTupleStruct for SerializeList<S> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializes a map of `Value`s, whose keys must serialize as strings.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SerializeMap<S: MultihashSize>(BTreeMap<String, Value<S>>, Option<String>);
+
+impl<S: MultihashSize> ser::SerializeMap for SerializeMap<S> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = key.serialize(MapKeySerializer)?;
+        if self.0.contains_key(&key) {
+            return Err(<Error as ser::Error>::custom(format!(
+                "duplicate map key `{}`",
+                key
+            )));
+        }
+        self.1 = Some(key);
+        Ok(())
+    }
+
+    #[inline]
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .1
+            .take()
+            .ok_or_else(|| <Error as ser::Error>::custom("map value serialized before its key"))?;
+        self.0.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Map(self.0))
+    }
+}
+
+impl<S: MultihashSize> ser::SerializeStruct for SerializeMap<S> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.0.insert(key.into(), to_value(value)?);
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Value::Map(self.0))
+    }
+}
+
+/// Serializes an enum variant as a single-entry map of the variant name to
+/// its contents.
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct SerializeVariant<T>(&'static str, T);
+
+impl<S: MultihashSize> ser::SerializeTupleVariant for SerializeVariant<SerializeList<S>> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(&mut self.1, value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let list = ser::SerializeSeq::end(self.1)?;
+        Ok(variant_map(self.0, list))
+    }
+}
+
+impl<S: MultihashSize> ser::SerializeStructVariant for SerializeVariant<SerializeMap<S>> {
+    type Ok = Value<S>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeStruct::serialize_field(&mut self.1, key, value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let map = ser::SerializeStruct::end(self.1)?;
+        Ok(variant_map(self.0, map))
+    }
+}
+
+#[inline]
+fn variant_map<S: MultihashSize>(variant: &'static str, value: Value<S>) -> Value<S> {
+    let mut map = BTreeMap::new();
+    map.insert(variant.into(), value);
+    Value::Map(map)
+}
+
+/// Serializes a map key, which must be a string.
+struct MapKeySerializer;
+
+impl MapKeySerializer {
+    #[inline]
+    fn key_error() -> Error {
+        <Error as ser::Error>::custom("IPLD map keys must be strings")
+    }
+}
+
+macro_rules! serialize_invalid_key {
+    ($($type:ty : $serialize_fn:ident,)*) => {
+        $(
+            #[inline]
+            fn $serialize_fn(self, _: $type) -> Result<Self::Ok, Self::Error> {
+                Err(Self::key_error())
+            }
+        )*
+    };
+}
+
+impl Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    serialize_invalid_key! {
+        bool: serialize_bool,
+        i8: serialize_i8,
+        i16: serialize_i16,
+        i32: serialize_i32,
+        i64: serialize_i64,
+        u8: serialize_u8,
+        u16: serialize_u16,
+        u32: serialize_u32,
+        u64: serialize_u64,
+        f32: serialize_f32,
+        f64: serialize_f64,
+        &[u8]: serialize_bytes,
+    }
+
+    #[inline]
+    fn serialize_char(self, c: char) -> Result<Self::Ok, Self::Error> {
+        Ok(c.to_string())
+    }
+
+    #[inline]
+    fn serialize_str(self, s: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(s.into())
+    }
+
+    #[inline]
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_some<T>(self, _: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(variant.into())
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(Self::key_error())
+    }
+
+    #[inline]
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Self::key_error())
+    }
+}